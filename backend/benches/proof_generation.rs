@@ -0,0 +1,98 @@
+#![feature(generic_const_exprs)]
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use num_bigint::BigUint;
+use summa_backend::apis::round::Snapshot;
+use summa_solvency::merkle_sum_tree::{Cryptocurrency, Entry, MerkleSumTree, Tree};
+
+const SAMPLE_SIZE: usize = 10;
+const N_BYTES: usize = 8;
+const USER_INDEX: usize = 0;
+
+/// Builds a `Snapshot` over a tree of `2^LEVELS` users, each holding `1000 + i` of every currency.
+///
+/// The trusted setup is read from `ptau/hermez-raw-{k}`, see the `Prerequisites` section of the README to download it.
+fn build_snapshot<const LEVELS: usize, const N_CURRENCIES: usize>(
+    k: u32,
+) -> Snapshot<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    let entries = (0..2usize.pow(LEVELS as u32))
+        .map(|i| {
+            Entry::new(
+                format!("user{}", i),
+                [BigUint::from(1000 + i); N_CURRENCIES],
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let cryptocurrencies = (0..N_CURRENCIES)
+        .map(|i| Cryptocurrency {
+            name: format!("TOKEN{}", i),
+            chain: "ETH".to_string(),
+        })
+        .collect();
+
+    let mst =
+        MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries, cryptocurrencies, false)
+            .unwrap();
+
+    Snapshot::<LEVELS, N_CURRENCIES, N_BYTES>::new(Box::new(mst), &format!("ptau/hermez-raw-{}", k))
+        .unwrap()
+}
+
+fn bench_proof_of_inclusion<const LEVELS: usize, const N_CURRENCIES: usize>(k: u32)
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    // The snapshot is built once, outside of the measurements
+    let snapshot = build_snapshot::<LEVELS, N_CURRENCIES>(k);
+    let proof = snapshot.generate_proof_of_inclusion(USER_INDEX).unwrap();
+
+    let mut group = criterion.benchmark_group(format!(
+        "proof of inclusion - tree of 2 power of {} entries with {} currencies",
+        LEVELS, N_CURRENCIES
+    ));
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("generate merkle proof", |b| {
+        b.iter(|| snapshot.mst.generate_proof(USER_INDEX).unwrap())
+    });
+
+    group.bench_function("generate zk proof", |b| {
+        b.iter(|| snapshot.generate_proof_of_inclusion(USER_INDEX).unwrap())
+    });
+
+    group.bench_function("serialize proof", |b| {
+        b.iter(|| serde_json::to_string(&proof).unwrap())
+    });
+
+    group.finish();
+}
+
+fn proof_of_inclusion_64_users_2_currencies(_c: &mut Criterion) {
+    bench_proof_of_inclusion::<6, 2>(12);
+}
+
+fn proof_of_inclusion_256_users_4_currencies(_c: &mut Criterion) {
+    bench_proof_of_inclusion::<8, 4>(13);
+}
+
+fn proof_of_inclusion_1024_users_8_currencies(_c: &mut Criterion) {
+    bench_proof_of_inclusion::<10, 8>(14);
+}
+
+criterion_group!(
+    benches,
+    proof_of_inclusion_64_users_2_currencies,
+    proof_of_inclusion_256_users_4_currencies,
+    proof_of_inclusion_1024_users_8_currencies,
+);
+criterion_main!(benches);
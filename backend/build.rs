@@ -2,6 +2,10 @@ use ethers::prelude::Abigen;
 use std::{fs::OpenOptions, io::Write, path::PathBuf};
 
 fn main() {
+    // Generate the gRPC server of the `SummaProver` service, this requires `protoc`
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/summa.proto").unwrap();
+
     let contracts = [
         (
             "src/contracts/generated/summa_contract.rs",
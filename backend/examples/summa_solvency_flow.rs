@@ -76,7 +76,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // 3. Generate Inclusion Proof
     //
     // Generate and export the inclusion proof for the specified user to a JSON file.
-    let inclusion_proof = round.get_proof_of_inclusion(USER_INDEX).unwrap();
+    let inclusion_proof = round.get_proof_of_inclusion(USER_INDEX).await.unwrap();
 
     let filename = format!("user_{}_proof.json", USER_INDEX);
     let mut file = File::create(filename.clone()).expect("Unable to create file");
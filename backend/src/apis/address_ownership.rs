@@ -1,18 +1,19 @@
 use crate::contracts::{generated::summa_contract::AddressOwnershipProof, signer::SummaSigner};
+use ethers::signers::{LocalWallet, Signer};
 use std::{error::Error, result::Result};
 
 use super::csv_parser::parse_signature_csv;
 
-pub struct AddressOwnership<'a> {
+pub struct AddressOwnership<'a, S = LocalWallet> {
     address_ownership_proofs: Vec<AddressOwnershipProof>,
-    signer: &'a SummaSigner,
+    signer: &'a SummaSigner<S>,
 }
 
-impl AddressOwnership<'_> {
+impl<S: Signer + 'static> AddressOwnership<'_, S> {
     pub fn new<'a>(
-        signer: &'a SummaSigner,
+        signer: &'a SummaSigner<S>,
         signature_csv_path: &str,
-    ) -> Result<AddressOwnership<'a>, Box<dyn Error>> {
+    ) -> Result<AddressOwnership<'a, S>, Box<dyn Error>> {
         let address_ownership_proofs = parse_signature_csv(signature_csv_path)?;
 
         Ok(AddressOwnership {
@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    utils::{hex, keccak256},
+};
+use std::{
+    error::Error,
+    io::Write,
+    net::IpAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::apis::round::{MstInclusionProof, Round, RoundError, Snapshot};
+
+/// A source of proofs of inclusion, such as a `Round` or a `Snapshot`.
+#[async_trait]
+pub trait ProofGenerator {
+    async fn generate_proof_of_inclusion(
+        &self,
+        user_index: usize,
+    ) -> Result<MstInclusionProof, RoundError>;
+}
+
+/// A `Snapshot` generates the proof on the calling task, use a `Round` to generate it on the blocking thread pool of the Tokio runtime.
+#[async_trait]
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> ProofGenerator
+    for Snapshot<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    async fn generate_proof_of_inclusion(
+        &self,
+        user_index: usize,
+    ) -> Result<MstInclusionProof, RoundError> {
+        Snapshot::generate_proof_of_inclusion(self, user_index).map_err(RoundError::ProofGeneration)
+    }
+}
+
+#[async_trait]
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize, S: Signer + 'static>
+    ProofGenerator for Round<'_, LEVELS, N_CURRENCIES, N_BYTES, S>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    async fn generate_proof_of_inclusion(
+        &self,
+        user_index: usize,
+    ) -> Result<MstInclusionProof, RoundError> {
+        self.get_proof_of_inclusion(user_index).await
+    }
+}
+
+/// A record of a proof of inclusion request.
+///
+/// Fields:
+/// * `timestamp`: The Unix time of the request, in seconds
+/// * `caller_ip`: The IP address of the caller, if known
+/// * `user_index`: The index of the user whose proof was requested
+/// * `proof_hash`: The keccak256 hash of the proof calldata, all zeros if the proof generation failed
+/// * `success`: Whether the proof was generated
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplianceEntry {
+    pub timestamp: u64,
+    pub caller_ip: Option<IpAddr>,
+    pub user_index: usize,
+    pub proof_hash: [u8; 32],
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceAuditLog {
+    entries: Vec<ComplianceEntry>,
+}
+
+impl ComplianceAuditLog {
+    pub fn get_entries(&self) -> &Vec<ComplianceEntry> {
+        &self.entries
+    }
+
+    /// Writes the log as CSV with the header `timestamp,caller_ip,user_index,proof_hash,success`.
+    /// The proof hash is hex encoded and an unknown caller IP is left empty.
+    pub fn export_csv(&self, writer: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        let mut csv_writer = csv::Writer::from_writer(writer);
+        csv_writer.write_record([
+            "timestamp",
+            "caller_ip",
+            "user_index",
+            "proof_hash",
+            "success",
+        ])?;
+
+        for entry in self.entries.iter() {
+            csv_writer.write_record([
+                entry.timestamp.to_string(),
+                entry.caller_ip.map(|ip| ip.to_string()).unwrap_or_default(),
+                entry.user_index.to_string(),
+                format!("0x{}", hex::encode(entry.proof_hash)),
+                entry.success.to_string(),
+            ])?;
+        }
+
+        csv_writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Records every proof of inclusion request made through it before returning the result of the inner `ProofGenerator`.
+pub struct ComplianceAuditor<R: ProofGenerator> {
+    inner: R,
+    log: ComplianceAuditLog,
+}
+
+impl<R: ProofGenerator> ComplianceAuditor<R> {
+    pub fn new(inner: R) -> Self {
+        ComplianceAuditor {
+            inner,
+            log: ComplianceAuditLog::default(),
+        }
+    }
+
+    pub fn get_log(&self) -> &ComplianceAuditLog {
+        &self.log
+    }
+
+    pub async fn get_proof_of_inclusion(
+        &mut self,
+        user_index: usize,
+        caller_ip: Option<IpAddr>,
+    ) -> Result<MstInclusionProof, RoundError> {
+        let result = self.inner.generate_proof_of_inclusion(user_index).await;
+
+        let proof_hash = match &result {
+            Ok(proof) => keccak256(proof.get_proof()),
+            Err(_) => [0u8; 32],
+        };
+
+        self.log.entries.push(ComplianceEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            caller_ip,
+            user_index,
+            proof_hash,
+            success: result.is_ok(),
+        });
+
+        result
+    }
+}
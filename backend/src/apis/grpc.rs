@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::apis::round::{RoundError, Snapshot};
+
+/// The types and the server generated by `tonic-build` from `proto/summa.proto`
+pub mod proto {
+    tonic::include_proto!("summa");
+}
+
+pub use proto::summa_prover_server::{SummaProver, SummaProverServer};
+use proto::{InclusionProofRequest, InclusionProofResponse};
+
+/// Serves the proofs of inclusion of a snapshot over gRPC.
+///
+/// The proofs are generated on the blocking thread pool of the Tokio runtime, as by `Round::get_proof_of_inclusion`.
+pub struct SummaProverService<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+{
+    snapshot: Arc<Snapshot<LEVELS, N_CURRENCIES, N_BYTES>>,
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    SummaProverService<LEVELS, N_CURRENCIES, N_BYTES>
+{
+    pub fn new(snapshot: Arc<Snapshot<LEVELS, N_CURRENCIES, N_BYTES>>) -> Self {
+        SummaProverService { snapshot }
+    }
+}
+
+/// Maps a failed proof generation to the status returned to the client
+fn to_status(error: RoundError) -> Status {
+    match error {
+        RoundError::ProofGeneration(_) => Status::invalid_argument(error.to_string()),
+        RoundError::RateLimited { .. } => Status::resource_exhausted(error.to_string()),
+        RoundError::ClaimedValueMismatch => Status::failed_precondition(error.to_string()),
+        RoundError::TaskPanic(_) => Status::internal(error.to_string()),
+    }
+}
+
+#[tonic::async_trait]
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> SummaProver
+    for SummaProverService<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+    [(); N_CURRENCIES + 2]: Sized,
+{
+    async fn get_inclusion_proof(
+        &self,
+        request: Request<InclusionProofRequest>,
+    ) -> Result<Response<InclusionProofResponse>, Status> {
+        let user_index = usize::try_from(request.into_inner().user_index)
+            .map_err(|_| Status::invalid_argument("The user index is out of range"))?;
+
+        let proof = Arc::clone(&self.snapshot)
+            .spawn_proof_of_inclusion(user_index)
+            .await
+            .map_err(to_status)?;
+
+        let public_inputs = proof
+            .get_public_inputs()
+            .iter()
+            .map(|input| {
+                let mut word = [0u8; 32];
+                input.to_big_endian(&mut word);
+                word.to_vec()
+            })
+            .collect();
+
+        Ok(Response::new(InclusionProofResponse {
+            proof_calldata: proof.get_proof().to_vec(),
+            public_inputs,
+        }))
+    }
+}
@@ -1,6 +1,11 @@
 pub mod address_ownership;
+pub mod compliance;
 pub mod csv_parser;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod proof_cache;
 pub mod round;
+pub mod verifying_key;
 
 use ethers::types::U256;
 use num_bigint::BigUint;
@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::apis::round::MstInclusionProof;
+
+/// The size of a record header: the user index as 2 little endian bytes, then the proof length as 4 little endian bytes
+const RECORD_HEADER_SIZE: usize = 6;
+
+#[derive(Debug)]
+pub enum CacheError {
+    /// Reading or writing the log file failed
+    Io(io::Error),
+    /// A complete record of the log doesn't contain a valid proof
+    Serialization(serde_json::Error),
+    /// The proof generation failed
+    ProofGeneration(&'static str),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(e) => write!(f, "Proof cache log error: {}", e),
+            CacheError::Serialization(e) => write!(f, "Invalid proof in the cache log: {}", e),
+            CacheError::ProofGeneration(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(e: io::Error) -> Self {
+        CacheError::Io(e)
+    }
+}
+
+/// A cache of proofs of inclusion backed by a write-ahead log, so that the proofs generated before a crash are not lost.
+///
+/// Each proof is appended to the log as `[user_index_le: 2 bytes][proof_len: 4 bytes][proof_bytes: proof_len bytes]` before being added to the cache,
+/// where the proof bytes are the JSON serialization of the `MstInclusionProof`.
+/// A record cut short by a crash is dropped when the log is replayed.
+#[derive(Debug)]
+pub struct OptimisticProofCache {
+    log_path: PathBuf,
+    proofs: HashMap<u16, MstInclusionProof>,
+}
+
+impl OptimisticProofCache {
+    /// Opens the cache logged at `log_path`, creating the log if it doesn't exist and replaying it otherwise
+    pub fn new(log_path: &Path) -> Result<Self, CacheError> {
+        if !log_path.exists() {
+            File::create(log_path)?;
+        }
+
+        Self::resume_from_log(log_path)
+    }
+
+    /// Rebuilds the cache from an existing log, typically after a crash
+    pub fn resume_from_log(path: &Path) -> Result<Self, CacheError> {
+        let mut log = Vec::new();
+        File::open(path)?.read_to_end(&mut log)?;
+
+        let mut proofs = HashMap::new();
+        let mut offset = 0;
+        while offset + RECORD_HEADER_SIZE <= log.len() {
+            let user_index = u16::from_le_bytes([log[offset], log[offset + 1]]);
+            let proof_len =
+                u32::from_le_bytes(log[offset + 2..offset + 6].try_into().unwrap()) as usize;
+
+            let proof_start = offset + RECORD_HEADER_SIZE;
+            if proof_start + proof_len > log.len() {
+                break;
+            }
+
+            let proof = serde_json::from_slice(&log[proof_start..proof_start + proof_len])
+                .map_err(CacheError::Serialization)?;
+            proofs.insert(user_index, proof);
+
+            offset = proof_start + proof_len;
+        }
+
+        // Drop the incomplete record left by a crash, so that new records are appended right after the last complete one
+        if offset < log.len() {
+            OpenOptions::new()
+                .write(true)
+                .open(path)?
+                .set_len(offset as u64)?;
+        }
+
+        Ok(OptimisticProofCache {
+            log_path: path.to_path_buf(),
+            proofs,
+        })
+    }
+
+    pub fn get_log_path(&self) -> &Path {
+        &self.log_path
+    }
+
+    pub fn get(&self, user_index: u16) -> Option<&MstInclusionProof> {
+        self.proofs.get(&user_index)
+    }
+
+    pub fn contains(&self, user_index: u16) -> bool {
+        self.proofs.contains_key(&user_index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Appends the proof of the user with index `user_index` to the log, then adds it to the cache.
+    /// The log is synced to disk before returning, so the proof survives a crash once this returns.
+    pub fn insert(&mut self, user_index: u16, proof: MstInclusionProof) -> Result<(), CacheError> {
+        self.insert_batch(vec![(user_index, proof)])
+    }
+
+    /// Appends the proofs, each paired with the index of its user, to the log, then adds them to the cache.
+    /// The log is synced to disk once for the whole batch before returning.
+    pub fn insert_batch(
+        &mut self,
+        proofs: Vec<(u16, MstInclusionProof)>,
+    ) -> Result<(), CacheError> {
+        if proofs.is_empty() {
+            return Ok(());
+        }
+
+        let mut records = Vec::new();
+        for (user_index, proof) in &proofs {
+            let proof_bytes = serde_json::to_vec(proof).map_err(CacheError::Serialization)?;
+
+            records.extend_from_slice(&user_index.to_le_bytes());
+            records.extend_from_slice(&(proof_bytes.len() as u32).to_le_bytes());
+            records.extend_from_slice(&proof_bytes);
+        }
+
+        let mut log = OpenOptions::new().append(true).open(&self.log_path)?;
+        log.write_all(&records)?;
+        log.sync_data()?;
+
+        self.proofs.extend(proofs);
+        Ok(())
+    }
+}
@@ -1,19 +1,53 @@
-use ethers::types::{Bytes, U256};
+use ethers::{
+    abi::AbiEncode,
+    signers::{LocalWallet, Signer},
+    types::{Bytes, U256},
+    utils::keccak256,
+};
 use halo2_proofs::{
-    halo2curves::bn256::{Bn256, G1Affine},
+    halo2curves::bn256::{Bn256, Fr as Fp, G1Affine},
     plonk::{ProvingKey, VerifyingKey},
     poly::kzg::commitment::ParamsKZG,
 };
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::contracts::{generated::summa_contract::summa::Cryptocurrency, signer::SummaSigner};
+#[cfg(feature = "grpc")]
+use crate::apis::grpc::{SummaProverServer, SummaProverService};
+use crate::{
+    apis::{
+        proof_cache::{CacheError, OptimisticProofCache},
+        verifying_key::VerificationKeyWrapper,
+    },
+    contracts::{
+        generated::summa_contract::summa::{Cryptocurrency, SubmitCommitmentCall},
+        signer::SummaSigner,
+    },
+};
 use summa_solvency::{
     circuits::{
         merkle_sum_tree::MstInclusionCircuit,
-        utils::{gen_proof_solidity_calldata, generate_setup_artifacts},
+        utils::{
+            field_element_to_solidity_calldata, gen_proof_solidity_calldata,
+            gen_proof_solidity_calldata_with_rng, generate_setup_artifacts,
+            verify_proof_solidity_calldata,
+        },
+        WithInstances,
     },
-    merkle_sum_tree::Tree,
+    merkle_sum_tree::{Entry, Node, Tree},
 };
 
 pub(crate) type SetupArtifacts = (
@@ -38,35 +72,251 @@ impl MstInclusionProof {
     }
 }
 
+/// The commitment of a round along with the verifying key of the inclusion circuit,
+/// so that auditors can verify the proofs of inclusion of the round without accessing the local filesystem.
+///
+/// The verifying key is serialized as a base64 string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundSummary<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub timestamp: u64,
+    pub mst_root: U256,
+    pub root_balances: Vec<U256>,
+    pub verifying_key: VerificationKeyWrapper<LEVELS, N_CURRENCIES, N_BYTES>,
+}
+
+/// A flag shared between a proof generation and the code that may cancel it.
+/// Clones of a token share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoundError {
+    /// A proof for `user_index` was already requested less than a cooldown ago
+    RateLimited {
+        user_index: usize,
+        retry_after: Duration,
+    },
+    /// The proof generation failed
+    ProofGeneration(&'static str),
+    /// The claimed values don't match the leaf of the user in the Merkle Sum Tree
+    ClaimedValueMismatch,
+    /// The blocking task generating the proof panicked or was cancelled
+    TaskPanic(String),
+}
+
+impl fmt::Display for RoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundError::RateLimited {
+                user_index,
+                retry_after,
+            } => write!(
+                f,
+                "Too many proof requests for user {}, retry after {:?}",
+                user_index, retry_after
+            ),
+            RoundError::ProofGeneration(message) => write!(f, "{}", message),
+            RoundError::ClaimedValueMismatch => {
+                write!(f, "The claimed values don't match the leaf of the user")
+            }
+            RoundError::TaskPanic(message) => {
+                write!(f, "The proof generation task failed: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RoundError {}
+
+/// The reason why the smoke test of a Snapshot failed.
+///
+/// The public inputs of a proof of inclusion are, in order, the leaf hash of the user, the root hash and the root balances.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SmokeTestError {
+    /// The proof of the user with index `user_index` couldn't be generated
+    ProofGeneration {
+        user_index: usize,
+        message: &'static str,
+    },
+    /// The public input with index `input_index` of the proof differs from the value derived from the Merkle Sum Tree
+    PublicInputMismatch {
+        user_index: usize,
+        input_index: usize,
+        computed: U256,
+        expected: U256,
+    },
+    /// The proof of the user with index `user_index` doesn't verify against the verification key of the Snapshot
+    VerificationFailed { user_index: usize },
+}
+
+impl fmt::Display for SmokeTestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmokeTestError::ProofGeneration {
+                user_index,
+                message,
+            } => write!(
+                f,
+                "Proof generation failed for user {}: {}",
+                user_index, message
+            ),
+            SmokeTestError::PublicInputMismatch {
+                user_index,
+                input_index,
+                computed,
+                expected,
+            } => write!(
+                f,
+                "Public input {} of the proof of user {} is {}, expected {}",
+                input_index, user_index, computed, expected
+            ),
+            SmokeTestError::VerificationFailed { user_index } => {
+                write!(f, "The proof of user {} doesn't verify", user_index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SmokeTestError {}
+
+/// The minimum number of tracked users from which the expired requests are pruned before a cooldown has passed since the last pruning
+const RATE_LIMITER_MIN_PRUNING_LEN: usize = 1024;
+
+/// Tracks the last proof request of each user to enforce a cooldown between two requests for the same user.
+///
+/// A request only looks up the last request of its own user. The requests at least a cooldown old are pruned lazily:
+/// once a cooldown has passed since the last pruning, or once the number of tracked users has doubled since then.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests: HashMap<usize, Instant>,
+    cooldown: Duration,
+    last_pruning: Option<Instant>,
+    next_pruning_len: usize,
+}
+
+impl RateLimiter {
+    pub fn new(cooldown: Duration) -> Self {
+        RateLimiter {
+            requests: HashMap::new(),
+            cooldown,
+            last_pruning: None,
+            next_pruning_len: RATE_LIMITER_MIN_PRUNING_LEN,
+        }
+    }
+
+    /// Records a request for `user_index` at `now`.
+    /// Returns the remaining cooldown instead if the previous request for `user_index` is less than a cooldown old.
+    pub fn check(&mut self, user_index: usize, now: Instant) -> Result<(), Duration> {
+        if let Some(last_request) = self.requests.get(&user_index) {
+            let elapsed = now.saturating_duration_since(*last_request);
+            if elapsed < self.cooldown {
+                return Err(self.cooldown - elapsed);
+            }
+        }
+
+        self.requests.insert(user_index, now);
+        self.prune_if_due(now);
+        Ok(())
+    }
+
+    /// Drops the requests at least a cooldown old if a cooldown has passed since the last pruning, or if the number of tracked users reached `next_pruning_len`.
+    /// As `next_pruning_len` is then set to twice the number of remaining users, the pruning cost is amortized over the requests.
+    fn prune_if_due(&mut self, now: Instant) {
+        let last_pruning = *self.last_pruning.get_or_insert(now);
+        if now.saturating_duration_since(last_pruning) < self.cooldown
+            && self.requests.len() < self.next_pruning_len
+        {
+            return;
+        }
+
+        let cooldown = self.cooldown;
+        self.requests
+            .retain(|_, last_request| now.saturating_duration_since(*last_request) < cooldown);
+
+        self.last_pruning = Some(now);
+        self.next_pruning_len = (2 * self.requests.len()).max(RATE_LIMITER_MIN_PRUNING_LEN);
+    }
+
+    /// Returns the number of tracked users, including the ones whose cooldown ended since the last pruning
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+/// A Round whose proofs of inclusion can be requested at most once per cooldown for each user.
+pub struct RateLimitedRound<
+    'a,
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    S = LocalWallet,
+> {
+    round: Round<'a, LEVELS, N_CURRENCIES, N_BYTES, S>,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+}
+
 pub struct Snapshot<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> {
-    pub mst: Box<dyn Tree<N_CURRENCIES>>,
+    pub mst: Box<dyn Tree<N_CURRENCIES> + Send + Sync>,
     trusted_setup: SetupArtifacts,
 }
 
-pub struct Round<'a, const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> {
+/// A round of the protocol, whose commitment is sent by a `SummaSigner` with the signer `S`.
+pub struct Round<
+    'a,
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+    S = LocalWallet,
+> {
     timestamp: u64,
-    snapshot: Snapshot<LEVELS, N_CURRENCIES, N_BYTES>,
-    signer: &'a SummaSigner,
+    snapshot: Arc<Snapshot<LEVELS, N_CURRENCIES, N_BYTES>>,
+    signer: &'a SummaSigner<S>,
 }
 
-impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
-    Round<'_, LEVELS, N_CURRENCIES, N_BYTES>
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize, S: Signer + 'static>
+    Round<'_, LEVELS, N_CURRENCIES, N_BYTES, S>
 where
     [usize; N_CURRENCIES + 1]: Sized,
     [usize; N_CURRENCIES + 2]: Sized,
 {
     pub fn new<'a>(
-        signer: &'a SummaSigner,
-        mst: Box<dyn Tree<N_CURRENCIES>>,
+        signer: &'a SummaSigner<S>,
+        mst: Box<dyn Tree<N_CURRENCIES> + Send + Sync>,
         params_path: &str,
         timestamp: u64,
-    ) -> Result<Round<'a, LEVELS, N_CURRENCIES, N_BYTES>, Box<dyn Error>>
+    ) -> Result<Round<'a, LEVELS, N_CURRENCIES, N_BYTES, S>, Box<dyn Error>>
     where
         [(); N_CURRENCIES + 2]: Sized,
     {
         Ok(Round {
             timestamp,
-            snapshot: Snapshot::<LEVELS, N_CURRENCIES, N_BYTES>::new(mst, params_path).unwrap(),
+            snapshot: Arc::new(
+                Snapshot::<LEVELS, N_CURRENCIES, N_BYTES>::new(mst, params_path).unwrap(),
+            ),
             signer: &signer,
         })
     }
@@ -75,7 +325,12 @@ where
         self.timestamp
     }
 
-    pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>> {
+    pub fn get_snapshot(&self) -> &Snapshot<LEVELS, N_CURRENCIES, N_BYTES> {
+        &self.snapshot
+    }
+
+    /// Returns the arguments of the `submitCommitment` call of the Summa contract for this round
+    fn submit_commitment_call(&self) -> SubmitCommitmentCall {
         let root_str = format!("{:?}", self.snapshot.mst.root().hash);
         let mst_root = U256::from_str_radix(&root_str, 16).unwrap();
 
@@ -86,40 +341,159 @@ where
             root_sums.push(U256::from_str_radix(&fp_str, 16).unwrap());
         }
 
+        SubmitCommitmentCall {
+            mst_root,
+            root_balances: root_sums,
+            cryptocurrencies: self
+                .snapshot
+                .mst
+                .cryptocurrencies()
+                .iter()
+                .map(|cryptocurrency| Cryptocurrency {
+                    name: cryptocurrency.name.clone(),
+                    chain: cryptocurrency.chain.clone(),
+                })
+                .collect(),
+            timestamp: U256::from(self.get_timestamp()),
+        }
+    }
+
+    pub async fn dispatch_commitment(&mut self) -> Result<(), Box<dyn Error>> {
+        let call = self.submit_commitment_call();
+
         self.signer
             .submit_commitment(
-                mst_root,
-                root_sums,
-                self.snapshot
-                    .mst
-                    .cryptocurrencies()
-                    .iter()
-                    .map(|cryptocurrency| Cryptocurrency {
-                        name: cryptocurrency.name.clone(),
-                        chain: cryptocurrency.chain.clone(),
-                    })
-                    .collect::<Vec<Cryptocurrency>>()
-                    .as_slice()
-                    .try_into()
-                    .unwrap(),
-                U256::from(self.get_timestamp()),
+                call.mst_root,
+                call.root_balances,
+                call.cryptocurrencies,
+                call.timestamp,
             )
             .await?;
 
         Ok(())
     }
 
-    pub fn get_proof_of_inclusion(
+    /// Returns the summary of the commitment submitted by `dispatch_commitment`, along with the verifying key of the snapshot
+    pub fn get_summary(&self) -> RoundSummary<LEVELS, N_CURRENCIES, N_BYTES> {
+        let call = self.submit_commitment_call();
+
+        RoundSummary {
+            timestamp: self.timestamp,
+            mst_root: call.mst_root,
+            root_balances: call.root_balances,
+            verifying_key: self.snapshot.get_verification_key(),
+        }
+    }
+
+    /// Returns the calldata of the `submitCommitment(uint256,uint256[],(string,string)[],uint256)` call sent by `dispatch_commitment`.
+    ///
+    /// The calldata is the 4-byte function selector followed by the ABI encoding of the root hash, the root balances, the cryptocurrencies and the timestamp.
+    /// Each `uint256` is encoded as a 32-byte big-endian word, so the field elements keep their canonical value.
+    pub fn get_commitment_for_contract_abi(&self) -> Vec<u8> {
+        self.submit_commitment_call().encode()
+    }
+
+    /// Generates the proof of inclusion for the user with index `user_index` without blocking the Tokio runtime, see `Snapshot::spawn_proof_of_inclusion`.
+    pub async fn get_proof_of_inclusion(
         &self,
         user_index: usize,
-    ) -> Result<MstInclusionProof, &'static str>
+    ) -> Result<MstInclusionProof, RoundError>
     where
         [(); N_CURRENCIES + 2]: Sized,
     {
-        Ok(self
-            .snapshot
-            .generate_proof_of_inclusion(user_index)
-            .unwrap())
+        Arc::clone(&self.snapshot)
+            .spawn_proof_of_inclusion(user_index)
+            .await
+    }
+
+    /// Returns a tonic gRPC server of the `SummaProver` service, which serves the proofs of inclusion of this round.
+    ///
+    /// The service only holds the snapshot of the round, not the signer, so it can outlive the round.
+    #[cfg(feature = "grpc")]
+    pub fn to_grpc_service(
+        &self,
+    ) -> SummaProverServer<SummaProverService<LEVELS, N_CURRENCIES, N_BYTES>>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        SummaProverServer::new(SummaProverService::new(Arc::clone(&self.snapshot)))
+    }
+
+    pub fn user_indices_by_balance_range(
+        &self,
+        currency_index: usize,
+        lo: &BigUint,
+        hi: &BigUint,
+    ) -> Result<Vec<usize>, &'static str> {
+        self.snapshot
+            .user_indices_by_balance_range(currency_index, lo, hi)
+    }
+
+    pub fn user_count_above_balance(
+        &self,
+        currency_index: usize,
+        threshold: &BigUint,
+    ) -> Result<usize, &'static str> {
+        self.snapshot
+            .user_count_above_balance(currency_index, threshold)
+    }
+}
+
+impl<'a, const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize, S>
+    Round<'a, LEVELS, N_CURRENCIES, N_BYTES, S>
+{
+    pub fn with_rate_limiter(
+        self,
+        cooldown: Duration,
+    ) -> RateLimitedRound<'a, LEVELS, N_CURRENCIES, N_BYTES, S> {
+        RateLimitedRound {
+            round: self,
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new(cooldown))),
+        }
+    }
+}
+
+impl<
+        'a,
+        const LEVELS: usize,
+        const N_CURRENCIES: usize,
+        const N_BYTES: usize,
+        S: Signer + 'static,
+    > RateLimitedRound<'a, LEVELS, N_CURRENCIES, N_BYTES, S>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn get_round(&self) -> &Round<'a, LEVELS, N_CURRENCIES, N_BYTES, S> {
+        &self.round
+    }
+
+    /// Returns the rate limiter, to share it with other rate limited rounds
+    pub fn get_rate_limiter(&self) -> Arc<Mutex<RateLimiter>> {
+        Arc::clone(&self.rate_limiter)
+    }
+
+    /// Generates the proof of inclusion for the user with index `user_index`, unless a proof for the same user was requested less than a cooldown ago.
+    /// A request counts toward the cooldown even if the proof generation fails.
+    ///
+    /// Like `Round::get_proof_of_inclusion`, the proof is generated on the blocking thread pool of the Tokio runtime.
+    pub async fn get_proof_of_inclusion(
+        &self,
+        user_index: usize,
+    ) -> Result<MstInclusionProof, RoundError>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        self.rate_limiter
+            .lock()
+            .unwrap()
+            .check(user_index, Instant::now())
+            .map_err(|retry_after| RoundError::RateLimited {
+                user_index,
+                retry_after,
+            })?;
+
+        self.round.get_proof_of_inclusion(user_index).await
     }
 }
 
@@ -130,7 +504,7 @@ where
     [usize; N_CURRENCIES + 2]: Sized,
 {
     pub fn new(
-        mst: Box<dyn Tree<N_CURRENCIES>>,
+        mst: Box<dyn Tree<N_CURRENCIES> + Send + Sync>,
         params_path: &str,
     ) -> Result<Snapshot<LEVELS, N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>> {
         let mst_inclusion_circuit =
@@ -150,6 +524,24 @@ where
         })
     }
 
+    pub fn get_verification_key(&self) -> VerificationKeyWrapper<LEVELS, N_CURRENCIES, N_BYTES> {
+        VerificationKeyWrapper(self.trusted_setup.2.clone())
+    }
+
+    fn inclusion_circuit(
+        &self,
+        user_index: usize,
+    ) -> Result<MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>, &'static str> {
+        let merkle_proof = self
+            .mst
+            .generate_proof(user_index)
+            .map_err(|_| "Failed to generate the merkle proof")?;
+
+        Ok(MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(
+            merkle_proof,
+        ))
+    }
+
     pub fn generate_proof_of_inclusion(
         &self,
         user_index: usize,
@@ -157,8 +549,7 @@ where
     where
         [(); N_CURRENCIES + 2]: Sized,
     {
-        let merkle_proof = self.mst.generate_proof(user_index).unwrap();
-        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+        let circuit = self.inclusion_circuit(user_index)?;
 
         // Currently, default manner of generating a inclusion proof for solidity-verifier.
         let calldata = gen_proof_solidity_calldata(
@@ -172,4 +563,490 @@ where
             public_inputs: calldata.1,
         })
     }
+
+    /// Generates the proof of inclusion for the user with index `user_index` on the blocking thread pool of the Tokio runtime,
+    /// so that the async tasks sharing the runtime are not stalled by the proof generation.
+    pub async fn spawn_proof_of_inclusion(
+        self: Arc<Self>,
+        user_index: usize,
+    ) -> Result<MstInclusionProof, RoundError>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        tokio::task::spawn_blocking(move || self.generate_proof_of_inclusion(user_index))
+            .await
+            .map_err(|join_error| RoundError::TaskPanic(join_error.to_string()))?
+            .map_err(RoundError::ProofGeneration)
+    }
+
+    /// Generates the proof of inclusion for the user with index `user_index` only if the leaf of the user is the hash of
+    /// `claimed_username` and `claimed_balances`, alongside those balances.
+    ///
+    /// This lets an auditor check that specific values are provable without trusting the entries stored by the exchange.
+    pub fn generate_proof_with_claimed_values(
+        &self,
+        user_index: usize,
+        claimed_username: Fp,
+        claimed_balances: &[Fp; N_CURRENCIES],
+    ) -> Result<MstInclusionProof, RoundError>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let merkle_proof = self
+            .mst
+            .generate_proof(user_index)
+            .map_err(|_| RoundError::ProofGeneration("Failed to generate the merkle proof"))?;
+
+        let mut claimed_leaf_preimage = [Fp::zero(); N_CURRENCIES + 1];
+        claimed_leaf_preimage[0] = claimed_username;
+        claimed_leaf_preimage[1..].copy_from_slice(claimed_balances);
+        let claimed_leaf = Node::<N_CURRENCIES>::leaf_node_from_preimage(&claimed_leaf_preimage);
+
+        if !self.mst.verify_proof_with_expected_leaf(
+            &merkle_proof,
+            claimed_leaf.hash,
+            claimed_leaf.balances,
+        ) {
+            return Err(RoundError::ClaimedValueMismatch);
+        }
+
+        let circuit = MstInclusionCircuit::<LEVELS, N_CURRENCIES, N_BYTES>::init(merkle_proof);
+        let calldata =
+            gen_proof_solidity_calldata(&self.trusted_setup.0, &self.trusted_setup.1, circuit);
+
+        Ok(MstInclusionProof {
+            proof_calldata: calldata.0,
+            public_inputs: calldata.1,
+        })
+    }
+
+    /// Generates the proof of inclusion for the user with index `user_index`, unless `token` gets cancelled.
+    ///
+    /// The token is checked before building the circuit, before proving and after proving. The halo2 prover itself can't be
+    /// interrupted, so a cancellation during proving takes effect once the prover returns, and the proof is discarded.
+    pub fn generate_proof_of_inclusion_cancellable(
+        &self,
+        user_index: usize,
+        token: &CancellationToken,
+    ) -> Result<MstInclusionProof, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let check_cancelled = || {
+            if token.is_cancelled() {
+                Err("Proof generation cancelled")
+            } else {
+                Ok(())
+            }
+        };
+
+        check_cancelled()?;
+        let circuit = self.inclusion_circuit(user_index)?;
+
+        check_cancelled()?;
+        let calldata =
+            gen_proof_solidity_calldata(&self.trusted_setup.0, &self.trusted_setup.1, circuit);
+
+        check_cancelled()?;
+        Ok(MstInclusionProof {
+            proof_calldata: calldata.0,
+            public_inputs: calldata.1,
+        })
+    }
+
+    /// Generates the proof of inclusion for the user with index `user_index` with the proof randomness derived from `secret_seed`,
+    /// the root hash of the Merkle Sum Tree and `user_index`.
+    ///
+    /// Two servers sharing the same snapshot and `secret_seed` produce bit-identical proofs for the same user.
+    /// The seed must be kept secret, as anyone knowing it can recompute the blinding factors of the proof.
+    pub fn generate_proof_of_inclusion_deterministic(
+        &self,
+        user_index: usize,
+        secret_seed: &[u8; 32],
+    ) -> Result<MstInclusionProof, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        let circuit = self.inclusion_circuit(user_index)?;
+
+        let mut seed_preimage = secret_seed.to_vec();
+        seed_preimage.extend_from_slice(&self.mst.root().hash.to_bytes());
+        seed_preimage.extend_from_slice(&(user_index as u64).to_le_bytes());
+        let rng = ChaCha20Rng::from_seed(keccak256(seed_preimage));
+
+        let calldata = gen_proof_solidity_calldata_with_rng(
+            &self.trusted_setup.0,
+            &self.trusted_setup.1,
+            circuit,
+            rng,
+        );
+
+        Ok(MstInclusionProof {
+            proof_calldata: calldata.0,
+            public_inputs: calldata.1,
+        })
+    }
+
+    /// Generates the proofs of inclusion for the users with index in `[start, end)` in parallel.
+    ///
+    /// The range must satisfy `start < end <= N_USERS`, where `N_USERS` is the number of leaves of the Merkle Sum Tree.
+    /// Each proof is returned along with its user index. A failure for a single user doesn't abort the generation of the other proofs.
+    pub fn generate_proofs_in_range(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<(usize, Result<MstInclusionProof, &'static str>)>, &'static str>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        if start >= end || end > self.mst.leaves_count() {
+            return Err("Invalid user index range");
+        }
+
+        let mut proofs = Vec::with_capacity(end - start);
+        (start..end)
+            .into_par_iter()
+            .map(|user_index| (user_index, self.generate_proof_of_inclusion(user_index)))
+            .collect_into_vec(&mut proofs);
+
+        Ok(proofs)
+    }
+
+    /// Generates the proofs of inclusion of the users with index in `0..n` and checks each of them.
+    ///
+    /// The public inputs of each proof must match the ones derived from the Merkle Sum Tree, and the proof must verify against the verification key of the Snapshot.
+    /// This is meant as a smoke test of the setup, for a small `n`.
+    pub fn smoke_test_first_n_users(&self, n: usize) -> Result<(), SmokeTestError>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        for user_index in 0..n {
+            let proof_generation_error = |message| SmokeTestError::ProofGeneration {
+                user_index,
+                message,
+            };
+
+            let expected_inputs: Vec<U256> = self
+                .inclusion_circuit(user_index)
+                .map_err(proof_generation_error)?
+                .instances()[0]
+                .iter()
+                .map(|input| field_element_to_solidity_calldata(*input))
+                .collect();
+
+            let proof = self
+                .generate_proof_of_inclusion(user_index)
+                .map_err(proof_generation_error)?;
+
+            for (input_index, (computed, expected)) in
+                proof.public_inputs.iter().zip(&expected_inputs).enumerate()
+            {
+                if computed != expected {
+                    return Err(SmokeTestError::PublicInputMismatch {
+                        user_index,
+                        input_index,
+                        computed: *computed,
+                        expected: *expected,
+                    });
+                }
+            }
+
+            if !verify_proof_solidity_calldata(
+                &self.trusted_setup.0,
+                &self.trusted_setup.2,
+                &proof.proof_calldata,
+                &proof.public_inputs,
+            ) {
+                return Err(SmokeTestError::VerificationFailed { user_index });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generates the proofs of inclusion of the users with index in `start..end` that are not yet in `cache`, then logs them to the cache.
+    ///
+    /// The proofs are generated in parallel, then the ones generated successfully are appended to the log together, with a single sync to disk.
+    /// A failing user doesn't stop the batch: the outcome of each generated proof is returned along with the index of its user.
+    pub fn generate_batch_proofs_of_inclusion(
+        &self,
+        start: u16,
+        end: u16,
+        cache: &mut OptimisticProofCache,
+    ) -> Result<Vec<(usize, Result<(), RoundError>)>, CacheError>
+    where
+        [(); N_CURRENCIES + 2]: Sized,
+    {
+        if start >= end || end as usize > self.mst.leaves_count() {
+            return Err(CacheError::ProofGeneration("Invalid user index range"));
+        }
+
+        let missing_user_indices: Vec<u16> = (start..end)
+            .filter(|user_index| !cache.contains(*user_index))
+            .collect();
+
+        let generated_proofs: Vec<(u16, Result<MstInclusionProof, RoundError>)> =
+            missing_user_indices
+                .into_par_iter()
+                .map(|user_index| {
+                    let proof = self
+                        .generate_proof_of_inclusion(user_index as usize)
+                        .map_err(RoundError::ProofGeneration);
+                    (user_index, proof)
+                })
+                .collect();
+
+        let mut results = Vec::with_capacity(generated_proofs.len());
+        let mut proofs = Vec::with_capacity(generated_proofs.len());
+        for (user_index, proof) in generated_proofs {
+            match proof {
+                Ok(proof) => {
+                    proofs.push((user_index, proof));
+                    results.push((user_index as usize, Ok(())));
+                }
+                Err(error) => results.push((user_index as usize, Err(error))),
+            }
+        }
+
+        cache.insert_batch(proofs)?;
+
+        Ok(results)
+    }
+
+    /// Returns the entries of the users along with their index.
+    /// The zero entries padding the Merkle Sum Tree are not counted as users.
+    fn user_entries(&self) -> impl Iterator<Item = (usize, &Entry<N_CURRENCIES>)> + '_ {
+        (0..self.mst.leaves_count())
+            .map(|index| (index, self.mst.get_entry(index)))
+            .filter(|(_, entry)| !entry.is_zero_entry())
+    }
+
+    /// Returns the balances of the users for the cryptocurrency with index `currency_index`, in ascending order.
+    fn sorted_user_balances(&self, currency_index: usize) -> Vec<BigUint> {
+        let mut balances: Vec<BigUint> = self
+            .user_entries()
+            .map(|(_, entry)| entry.balances()[currency_index].clone())
+            .collect();
+        balances.sort();
+
+        balances
+    }
+
+    /// Returns the balance at the given `percentile` (from 0.0 to 1.0) among the balances of the users for the cryptocurrency with index `currency_index`.
+    ///
+    /// The balance is picked by nearest rank, so the result is always the balance of an existing user.
+    pub fn user_balance_percentile(
+        &self,
+        currency_index: usize,
+        percentile: f64,
+    ) -> Result<BigUint, &'static str> {
+        if currency_index >= N_CURRENCIES {
+            return Err("Invalid currency index");
+        }
+
+        if !(0.0..=1.0).contains(&percentile) {
+            return Err("Percentile must be between 0.0 and 1.0");
+        }
+
+        let balances = self.sorted_user_balances(currency_index);
+        if balances.is_empty() {
+            return Err("No user in the snapshot");
+        }
+
+        let rank = ((balances.len() - 1) as f64 * percentile).round() as usize;
+
+        Ok(balances[rank].clone())
+    }
+
+    /// Splits the range of the balances of the users for the cryptocurrency with index `currency_index` into `n_buckets` buckets of equal width.
+    ///
+    /// Each bucket is returned as a `(bucket_low, bucket_high, count)` tuple, where `count` is the number of users with a balance in `[bucket_low, bucket_high)`.
+    /// An empty histogram is returned if the currency index is invalid, `n_buckets` is zero or the snapshot has no user.
+    pub fn balance_histogram(
+        &self,
+        currency_index: usize,
+        n_buckets: usize,
+    ) -> Vec<(BigUint, BigUint, usize)> {
+        if currency_index >= N_CURRENCIES || n_buckets == 0 {
+            return vec![];
+        }
+
+        let balances = self.sorted_user_balances(currency_index);
+        let (min, max) = match (balances.first(), balances.last()) {
+            (Some(min), Some(max)) => (min.clone(), max.clone()),
+            _ => return vec![],
+        };
+
+        // The width is rounded up so that the maximum balance falls into the last bucket
+        let width = (&max - &min) / BigUint::from(n_buckets) + 1u32;
+
+        let mut histogram: Vec<(BigUint, BigUint, usize)> = (0..n_buckets)
+            .map(|bucket| {
+                let bucket_low = &min + &width * BigUint::from(bucket);
+                let bucket_high = &bucket_low + &width;
+                (bucket_low, bucket_high, 0)
+            })
+            .collect();
+
+        for balance in balances.iter() {
+            let bucket = ((balance - &min) / &width).to_usize().unwrap();
+            histogram[bucket].2 += 1;
+        }
+
+        histogram
+    }
+
+    /// Returns the indices of the users whose balance for the cryptocurrency with index `currency_index` lies in `[lo, hi]`, in ascending order.
+    pub fn user_indices_by_balance_range(
+        &self,
+        currency_index: usize,
+        lo: &BigUint,
+        hi: &BigUint,
+    ) -> Result<Vec<usize>, &'static str> {
+        if currency_index >= N_CURRENCIES {
+            return Err("Invalid currency index");
+        }
+
+        Ok(self
+            .user_entries()
+            .filter(|(_, entry)| {
+                let balance = &entry.balances()[currency_index];
+                lo <= balance && balance <= hi
+            })
+            .map(|(index, _)| index)
+            .collect())
+    }
+
+    /// Returns the number of users whose balance for the cryptocurrency with index `currency_index` is strictly greater than `threshold`.
+    pub fn user_count_above_balance(
+        &self,
+        currency_index: usize,
+        threshold: &BigUint,
+    ) -> Result<usize, &'static str> {
+        if currency_index >= N_CURRENCIES {
+            return Err("Invalid currency index");
+        }
+
+        Ok(self
+            .user_entries()
+            .filter(|(_, entry)| entry.balances()[currency_index] > *threshold)
+            .count())
+    }
+
+    /// Returns the `(user_index, balance)` pairs of the users whose balance for the cryptocurrency with index `currency_index` is greater than `max_factor` times the mean balance, in ascending order of index.
+    ///
+    /// This is meant to catch data loading errors before committing, such as a balance expressed in the wrong unit.
+    /// No user is returned if the currency index is invalid or the snapshot has no user.
+    pub fn detect_balance_anomalies(
+        &self,
+        currency_index: usize,
+        max_factor: f64,
+    ) -> Vec<(usize, BigUint)> {
+        if currency_index >= N_CURRENCIES {
+            return vec![];
+        }
+
+        let (users_count, total) = self.user_entries().fold(
+            (0usize, BigUint::from(0u32)),
+            |(count, total), (_, entry)| (count + 1, total + &entry.balances()[currency_index]),
+        );
+        if users_count == 0 {
+            return vec![];
+        }
+
+        let threshold = total.to_f64().unwrap() / users_count as f64 * max_factor;
+
+        self.user_entries()
+            .map(|(index, entry)| (index, &entry.balances()[currency_index]))
+            .filter(|(_, balance)| balance.to_f64().unwrap() > threshold)
+            .map(|(index, balance)| (index, balance.clone()))
+            .collect()
+    }
+
+    /// Returns the fraction of the users whose balances are all zero, or 0.0 if the snapshot has no user.
+    ///
+    /// A high fraction usually means that some balances were not loaded.
+    pub fn detect_zero_balance_anomaly_fraction(&self) -> f64 {
+        let (users_count, zero_balance_count) =
+            self.user_entries()
+                .fold((0usize, 0usize), |(count, zero_count), (_, entry)| {
+                    let has_zero_balances = entry
+                        .balances()
+                        .iter()
+                        .all(|balance| *balance == BigUint::from(0u32));
+                    (count + 1, zero_count + usize::from(has_zero_balances))
+                });
+
+        if users_count == 0 {
+            return 0.0;
+        }
+
+        zero_balance_count as f64 / users_count as f64
+    }
+
+    /// Returns the Shannon entropy, in bits, of the balances of the users for the cryptocurrency with index `currency_index`, bucketed into the 256 buckets of `balance_histogram`.
+    ///
+    /// This is a statistical utility to inspect the distribution of the balances, it has no cryptographic significance.
+    pub fn balance_entropy(&self, currency_index: usize) -> Result<f64, &'static str> {
+        if currency_index >= N_CURRENCIES {
+            return Err("Invalid currency index");
+        }
+
+        let histogram = self.balance_histogram(currency_index, 256);
+        let users_count: usize = histogram.iter().map(|bucket| bucket.2).sum();
+
+        Ok(histogram
+            .iter()
+            .filter(|bucket| bucket.2 > 0)
+            .map(|bucket| {
+                let p = bucket.2 as f64 / users_count as f64;
+                -p * p.log2()
+            })
+            .sum())
+    }
+
+    /// Returns the Pearson correlation coefficient between the balances of the users for the cryptocurrencies with index `currency_a` and `currency_b`.
+    ///
+    /// This is a statistical utility to inspect the distribution of the balances, it has no cryptographic significance.
+    pub fn balance_correlation(
+        &self,
+        currency_a: usize,
+        currency_b: usize,
+    ) -> Result<f64, &'static str> {
+        if currency_a >= N_CURRENCIES || currency_b >= N_CURRENCIES {
+            return Err("Invalid currency index");
+        }
+
+        let (balances_a, balances_b): (Vec<f64>, Vec<f64>) = self
+            .user_entries()
+            .map(|(_, entry)| {
+                (
+                    entry.balances()[currency_a].to_f64().unwrap(),
+                    entry.balances()[currency_b].to_f64().unwrap(),
+                )
+            })
+            .unzip();
+
+        if balances_a.is_empty() {
+            return Err("No user in the snapshot");
+        }
+
+        let mean_a = balances_a.iter().sum::<f64>() / balances_a.len() as f64;
+        let mean_b = balances_b.iter().sum::<f64>() / balances_b.len() as f64;
+
+        let mut covariance = 0.0;
+        let mut variance_a = 0.0;
+        let mut variance_b = 0.0;
+        for (a, b) in balances_a.iter().zip(balances_b.iter()) {
+            covariance += (a - mean_a) * (b - mean_b);
+            variance_a += (a - mean_a).powi(2);
+            variance_b += (b - mean_b).powi(2);
+        }
+
+        if variance_a == 0.0 || variance_b == 0.0 {
+            return Err("The balances of a cryptocurrency are all equal");
+        }
+
+        Ok(covariance / (variance_a * variance_b).sqrt())
+    }
 }
@@ -0,0 +1,91 @@
+use halo2_proofs::{halo2curves::bn256::G1Affine, plonk::VerifyingKey, SerdeFormat};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{fmt, io};
+use summa_solvency::circuits::merkle_sum_tree::MstInclusionCircuit;
+
+#[derive(Debug)]
+pub enum VkError {
+    /// The input is not valid base64
+    Base64(base64::DecodeError),
+    /// The decoded bytes are not a valid verifying key
+    Io(io::Error),
+}
+
+impl fmt::Display for VkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VkError::Base64(e) => write!(f, "Invalid base64 verifying key: {}", e),
+            VkError::Io(e) => write!(f, "Invalid verifying key: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VkError {}
+
+/// A wrapper around the verifying key of the `MstInclusionCircuit` that can be distributed as a base64 string.
+///
+/// The key is encoded with halo2's `SerdeFormat::Processed`, so decoding checks that the points are on the curve.
+/// The circuit parameters are needed to rebuild the constraint system when decoding the key.
+#[derive(Debug, Clone)]
+pub struct VerificationKeyWrapper<
+    const LEVELS: usize,
+    const N_CURRENCIES: usize,
+    const N_BYTES: usize,
+>(pub VerifyingKey<G1Affine>);
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize>
+    VerificationKeyWrapper<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.0
+            .write(&mut bytes, SerdeFormat::Processed)
+            .expect("Writing to a vector never fails");
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, VkError> {
+        let vk = VerifyingKey::<G1Affine>::read::<
+            _,
+            MstInclusionCircuit<LEVELS, N_CURRENCIES, N_BYTES>,
+        >(&mut io::Cursor::new(bytes), SerdeFormat::Processed)
+        .map_err(VkError::Io)?;
+
+        Ok(VerificationKeyWrapper(vk))
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(self.to_bytes())
+    }
+
+    pub fn from_base64(s: &str) -> Result<Self, VkError> {
+        let bytes = base64::decode(s).map_err(VkError::Base64)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl<const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Serialize
+    for VerificationKeyWrapper<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_base64())
+    }
+}
+
+impl<'de, const LEVELS: usize, const N_CURRENCIES: usize, const N_BYTES: usize> Deserialize<'de>
+    for VerificationKeyWrapper<LEVELS, N_CURRENCIES, N_BYTES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_base64(&s).map_err(de::Error::custom)
+    }
+}
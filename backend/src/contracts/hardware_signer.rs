@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Signature,
+    },
+};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HardwareSignerError {
+    /// The device is not connected
+    DeviceNotFound,
+    /// `count` Ledgers are connected, the transport can only tell apart the first one
+    MultipleDevices(usize),
+    /// The device failed to sign, e.g. because it is locked or the user rejected the request on the device
+    Device(String),
+    /// The request can't be signed by the device
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for HardwareSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HardwareSignerError::DeviceNotFound => {
+                write!(f, "The hardware wallet is not connected")
+            }
+            HardwareSignerError::MultipleDevices(count) => write!(
+                f,
+                "{} Ledgers are connected, disconnect all of them but the signing one",
+                count
+            ),
+            HardwareSignerError::Device(message) => {
+                write!(f, "The hardware wallet failed to sign: {}", message)
+            }
+            HardwareSignerError::Unsupported(request) => {
+                write!(f, "The hardware wallet can't sign {}", request)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HardwareSignerError {}
+
+/// The device holding the signing key of a `SummaHardwareSigner`.
+///
+/// `LedgerWallet`, behind the `hardware-signing` feature, talks to a Ledger over USB.
+/// Any other device, or a mock of one, can be used by implementing this trait.
+#[async_trait]
+pub trait HardwareWallet: fmt::Debug + Send + Sync {
+    /// Returns the address of the signing key
+    fn address(&self) -> Address;
+
+    /// Shows the transaction on the device, then signs it once the user confirms it there
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<Signature, HardwareSignerError>;
+
+    /// Shows the message on the device, then signs it once the user confirms it there
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, HardwareSignerError>;
+}
+
+/// A signer whose key never leaves a hardware wallet, to be passed to `SummaSigner::with_signer`.
+///
+/// Each transaction sent by the `SummaSigner`, such as the commitment sent by `Round::dispatch_commitment`,
+/// is only signed once the user confirms it on the device.
+#[derive(Debug)]
+pub struct SummaHardwareSigner<W> {
+    wallet: W,
+    chain_id: u64,
+}
+
+impl<W: HardwareWallet> SummaHardwareSigner<W> {
+    /// Creates a signer for `wallet`. The chain id is set by `SummaSigner::with_signer` to the one of the node.
+    pub fn new(wallet: W) -> Self {
+        SummaHardwareSigner {
+            wallet,
+            chain_id: 1,
+        }
+    }
+
+    pub fn get_wallet(&self) -> &W {
+        &self.wallet
+    }
+}
+
+#[async_trait]
+impl<W: HardwareWallet> Signer for SummaHardwareSigner<W> {
+    type Error = HardwareSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        self.wallet.sign_message(message.as_ref()).await
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+
+        self.wallet.sign_transaction(&tx).await
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(HardwareSignerError::Unsupported("typed data"))
+    }
+
+    fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+/// The USB vendor id of Ledger
+#[cfg(feature = "hardware-signing")]
+const LEDGER_VENDOR_ID: u16 = 0x2c97;
+
+/// A Ledger connected over USB, as listed by `SummaHardwareSigner::list_available_devices`
+#[cfg(feature = "hardware-signing")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerDevice {
+    pub path: String,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+/// A Ledger running the Ethereum app, reached through the `coins-ledger` transport of ethers
+#[cfg(feature = "hardware-signing")]
+#[derive(Debug)]
+pub struct LedgerWallet(ethers::signers::Ledger);
+
+#[cfg(feature = "hardware-signing")]
+#[async_trait]
+impl HardwareWallet for LedgerWallet {
+    fn address(&self) -> Address {
+        Signer::address(&self.0)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &TypedTransaction,
+    ) -> Result<Signature, HardwareSignerError> {
+        Signer::sign_transaction(&self.0, tx)
+            .await
+            .map_err(|e| HardwareSignerError::Device(e.to_string()))
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, HardwareSignerError> {
+        Signer::sign_message(&self.0, message)
+            .await
+            .map_err(|e| HardwareSignerError::Device(e.to_string()))
+    }
+}
+
+#[cfg(feature = "hardware-signing")]
+impl SummaHardwareSigner<LedgerWallet> {
+    /// Returns the Ledgers connected over USB
+    pub fn list_available_devices() -> Vec<LedgerDevice> {
+        let api = match hidapi_rusb::HidApi::new() {
+            Ok(api) => api,
+            Err(_) => return Vec::new(),
+        };
+
+        // A Ledger exposes several HID interfaces, the APDU one is the first
+        api.device_list()
+            .filter(|info| info.vendor_id() == LEDGER_VENDOR_ID && info.interface_number() == 0)
+            .map(|info| LedgerDevice {
+                path: info.path().to_string_lossy().into_owned(),
+                product: info.product_string().map(str::to_string),
+                serial_number: info.serial_number().map(str::to_string),
+            })
+            .collect()
+    }
+
+    /// Connects to the Ethereum app of `device` and derives the signing key at `derivation_path`, e.g. `m/44'/60'/0'/0/0`.
+    ///
+    /// The transport opens the first Ledger it finds, so `device` must be the only Ledger connected.
+    pub async fn connect(
+        device: &LedgerDevice,
+        derivation_path: &str,
+    ) -> Result<Self, HardwareSignerError> {
+        let devices = Self::list_available_devices();
+        if !devices.contains(device) {
+            return Err(HardwareSignerError::DeviceNotFound);
+        }
+        if devices.len() > 1 {
+            return Err(HardwareSignerError::MultipleDevices(devices.len()));
+        }
+
+        let ledger = ethers::signers::Ledger::new(
+            ethers::signers::HDPath::Other(derivation_path.to_string()),
+            1,
+        )
+        .await
+        .map_err(|e| HardwareSignerError::Device(e.to_string()))?;
+
+        Ok(SummaHardwareSigner::new(LedgerWallet(ledger)))
+    }
+}
@@ -1,3 +1,4 @@
 pub mod generated;
+pub mod hardware_signer;
 pub mod mock;
 pub mod signer;
@@ -2,8 +2,9 @@ use ethers::{
     prelude::SignerMiddleware,
     providers::{Http, Middleware, Provider},
     signers::{LocalWallet, Signer},
-    types::{Address, U256},
+    types::{Address, BlockNumber, U256},
 };
+use futures::{Stream, StreamExt};
 use serde_json::Value;
 use std::{error::Error, fs::File, io::BufReader, path::Path, str::FromStr, sync::Arc};
 use tokio::sync::Mutex;
@@ -16,10 +17,15 @@ pub enum AddressInput {
     Path(String),
 }
 
+/// Sends the transactions of the exchange to the Summa contract.
+///
+/// The transactions are signed by `S`, a `LocalWallet` built from a private key by default.
+/// A `SummaHardwareSigner` keeps the key on a hardware wallet instead, see `with_signer`.
 #[derive(Debug)]
-pub struct SummaSigner {
+pub struct SummaSigner<S = LocalWallet> {
     nonce_lock: Mutex<()>, // To prevent running `submit` methods concurrently
-    summa_contract: Summa<SignerMiddleware<Arc<Provider<Http>>, LocalWallet>>,
+    provider: Arc<Provider<Http>>,
+    summa_contract: Summa<SignerMiddleware<Arc<Provider<Http>>, S>>,
 }
 
 impl SummaSigner {
@@ -35,11 +41,26 @@ impl SummaSigner {
     ) -> Result<Self, Box<dyn Error>> {
         let wallet: LocalWallet = LocalWallet::from_str(signer_key).unwrap();
 
+        Self::with_signer(wallet, url, address_input).await
+    }
+}
+
+impl<S: Signer + 'static> SummaSigner<S> {
+    /// Creates a new SummaSigner instance whose transactions are signed by `signer`, e.g. a `SummaHardwareSigner`
+    /// # Arguments
+    /// * `signer` - The signer that will interact with the chain on behalf of the exchange, its chain id is set to the one of the node
+    /// * `url` -  The endpoint for connecting to the node
+    /// * `address` - The address of the Summa contract
+    pub async fn with_signer(
+        signer: S,
+        url: &str,
+        address_input: AddressInput,
+    ) -> Result<Self, Box<dyn Error>> {
         let provider = Arc::new(Provider::try_from(url)?);
         let chain_id = provider.get_chainid().await?.as_u64();
         let client = Arc::new(SignerMiddleware::new(
-            provider,
-            wallet.with_chain_id(chain_id),
+            Arc::clone(&provider),
+            signer.with_chain_id(chain_id),
         ));
 
         let address = match address_input {
@@ -52,6 +73,7 @@ impl SummaSigner {
 
         Ok(Self {
             nonce_lock: Mutex::new(()),
+            provider,
             summa_contract: Summa::new(address, client),
         })
     }
@@ -60,6 +82,51 @@ impl SummaSigner {
         self.summa_contract.address()
     }
 
+    /// Returns the Merkle sum tree root committed on-chain for the round with the given `timestamp`.
+    /// A zero root means that no commitment has been submitted for that timestamp.
+    pub async fn get_committed_mst_root(&self, timestamp: u64) -> Result<U256, Box<dyn Error>> {
+        let mst_root = self
+            .summa_contract
+            .commitments(U256::from(timestamp))
+            .call()
+            .await?;
+
+        Ok(mst_root)
+    }
+
+    /// Returns the timestamp of the latest block.
+    /// The timestamp of a new round should be ahead of it.
+    pub async fn current_block_timestamp(&self) -> Result<u64, Box<dyn Error>> {
+        let block = self
+            .summa_contract
+            .client()
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or("Latest block not found")?;
+
+        Ok(block.timestamp.as_u64())
+    }
+
+    /// Returns a stream of the timestamps of the new blocks, as they are mined.
+    ///
+    /// The provider is HTTP, so the new blocks are polled with a block filter at the interval of the provider, then looked up one by one for their timestamp.
+    /// A block whose lookup fails is skipped.
+    pub async fn subscribe_to_block_timestamps(
+        &self,
+    ) -> Result<impl Stream<Item = u64> + '_, Box<dyn Error>> {
+        let provider = self.provider.as_ref();
+        let block_hashes = provider.watch_blocks().await?;
+
+        Ok(block_hashes.filter_map(move |block_hash| async move {
+            provider
+                .get_block(block_hash)
+                .await
+                .ok()
+                .flatten()
+                .map(|block| block.timestamp.as_u64())
+        }))
+    }
+
     fn get_deployment_address<P: AsRef<Path>>(
         path: P,
         chain_id: u64,
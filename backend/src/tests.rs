@@ -98,29 +98,176 @@ pub async fn initialize_test_env(
 
 #[cfg(test)]
 mod test {
+    use async_trait::async_trait;
     use ethers::{
-        abi::AbiEncode,
+        abi::{decode, AbiEncode, ParamType, Token},
+        contract::EthCall,
         providers::{Http, Middleware, Provider},
-        types::{U256, U64},
-        utils::to_checksum,
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, Signature, H160, U256, U64},
+        utils::{hex, keccak256, to_checksum, AnvilInstance},
+    };
+    use futures::StreamExt;
+    use num_bigint::BigUint;
+    use std::{
+        convert::TryFrom,
+        error::Error,
+        fs::OpenOptions,
+        io::Write,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+    use summa_solvency::merkle_sum_tree::{
+        utils::big_uint_to_fp, Entry, MerkleSumTree, OnChainFetchError, Tree,
     };
-    use std::{convert::TryFrom, error::Error};
-    use summa_solvency::merkle_sum_tree::MerkleSumTree;
     use tokio::{
         join,
-        time::{sleep, Duration},
+        time::{sleep, timeout, Duration},
     };
 
-    use crate::apis::{address_ownership::AddressOwnership, round::Round};
+    use crate::apis::{
+        address_ownership::AddressOwnership,
+        compliance::ComplianceAuditor,
+        proof_cache::{CacheError, OptimisticProofCache},
+        round::{
+            CancellationToken, RateLimiter, Round, RoundError, RoundSummary, SmokeTestError,
+            Snapshot,
+        },
+        verifying_key::{VerificationKeyWrapper, VkError},
+    };
     use crate::contracts::{
         generated::summa_contract::{
             AddressOwnershipProof, AddressOwnershipProofSubmittedFilter, Cryptocurrency,
-            LiabilitiesCommitmentSubmittedFilter,
+            LiabilitiesCommitmentSubmittedFilter, SubmitCommitmentCall,
         },
+        hardware_signer::{HardwareSignerError, HardwareWallet, SummaHardwareSigner},
+        mock::mock_erc20::MockERC20,
         signer::{AddressInput, SummaSigner},
     };
     use crate::tests::initialize_test_env;
 
+    /// The private key of the first Anvil account, which owns the Summa contract deployed by `initialize_test_env`
+    const SIGNER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    /// Returns a signer of the owner of the Summa contract deployed by `initialize_test_env`
+    async fn initialize_signer(anvil: &AnvilInstance, summa_address: H160) -> SummaSigner {
+        SummaSigner::new(
+            SIGNER_KEY,
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_address),
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Returns the Merkle Sum Tree of the 16 entries of `entry_16.csv`
+    fn initialize_mst() -> MerkleSumTree<2, 8> {
+        MerkleSumTree::<2, 8>::from_csv("../csv/entry_16.csv").unwrap()
+    }
+
+    /// Returns the snapshot of `initialize_mst`, with the included `ptau` file
+    fn initialize_snapshot() -> Snapshot<4, 2, 8> {
+        Snapshot::<4, 2, 8>::new(Box::new(initialize_mst()), "ptau/hermez-raw-11").unwrap()
+    }
+
+    /// Returns the round of `initialize_mst` at `timestamp`, whose commitment is sent by `signer`
+    fn initialize_round<S: Signer + 'static>(
+        signer: &SummaSigner<S>,
+        timestamp: u64,
+    ) -> Round<'_, 4, 2, 8, S> {
+        Round::<4, 2, 8, S>::new(
+            signer,
+            Box::new(initialize_mst()),
+            "ptau/hermez-raw-11",
+            timestamp,
+        )
+        .unwrap()
+    }
+
+    /// A Merkle Sum Tree that hides the leaf with index `hidden_index`, so that generating its proof fails
+    struct HiddenLeafTree {
+        mst: MerkleSumTree<2, 8>,
+        hidden_index: usize,
+    }
+
+    impl Tree<2> for HiddenLeafTree {
+        fn root(&self) -> &summa_solvency::merkle_sum_tree::Node<2> {
+            self.mst.root()
+        }
+
+        fn depth(&self) -> &usize {
+            self.mst.depth()
+        }
+
+        fn get_node(
+            &self,
+            level: usize,
+            index: usize,
+        ) -> Option<&summa_solvency::merkle_sum_tree::Node<2>> {
+            if level == 0 && index == self.hidden_index {
+                return None;
+            }
+            self.mst.get_node(level, index)
+        }
+
+        fn cryptocurrencies(&self) -> &[summa_solvency::merkle_sum_tree::Cryptocurrency] {
+            self.mst.cryptocurrencies()
+        }
+
+        fn get_entry(&self, index: usize) -> &Entry<2> {
+            self.mst.get_entry(index)
+        }
+    }
+
+    /// A hardware wallet holding a local key, whose user confirms the signing requests on the device if `confirm` is true
+    #[derive(Debug)]
+    struct MockHardwareWallet {
+        wallet: LocalWallet,
+        confirm: bool,
+        prompts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl HardwareWallet for MockHardwareWallet {
+        fn address(&self) -> H160 {
+            self.wallet.address()
+        }
+
+        async fn sign_transaction(
+            &self,
+            tx: &TypedTransaction,
+        ) -> Result<Signature, HardwareSignerError> {
+            self.prompts.fetch_add(1, Ordering::SeqCst);
+            if !self.confirm {
+                return Err(HardwareSignerError::Device(
+                    "The user rejected the transaction".to_string(),
+                ));
+            }
+
+            self.wallet
+                .sign_transaction(tx)
+                .await
+                .map_err(|e| HardwareSignerError::Device(e.to_string()))
+        }
+
+        async fn sign_message(&self, message: &[u8]) -> Result<Signature, HardwareSignerError> {
+            self.prompts.fetch_add(1, Ordering::SeqCst);
+            if !self.confirm {
+                return Err(HardwareSignerError::Device(
+                    "The user rejected the message".to_string(),
+                ));
+            }
+
+            self.wallet
+                .sign_message(message)
+                .await
+                .map_err(|e| HardwareSignerError::Device(e.to_string()))
+        }
+    }
+
     #[tokio::test]
     async fn test_deployed_address() -> Result<(), Box<dyn Error>> {
         let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
@@ -142,6 +289,46 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_current_block_timestamp() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, client, summa_contract) = initialize_test_env(None).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let latest_block = client.get_block(client.get_block_number().await?).await?;
+        assert_eq!(
+            signer.current_block_timestamp().await?,
+            latest_block.unwrap().timestamp.as_u64()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_to_block_timestamps() -> Result<(), Box<dyn Error>> {
+        // A block is mined every second
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(Some(1)).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let start_timestamp = signer.current_block_timestamp().await?;
+        let timestamps: Vec<u64> = timeout(
+            Duration::from_secs(60),
+            signer
+                .subscribe_to_block_timestamps()
+                .await?
+                .take(2)
+                .collect(),
+        )
+        .await?;
+
+        assert_eq!(timestamps.len(), 2);
+        assert!(start_timestamp <= timestamps[0]);
+        assert!(timestamps[0] < timestamps[1]);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_concurrent_sumbit_commitments() -> Result<(), Box<dyn Error>> {
         let (anvil, _, _, _, summa_contract) = initialize_test_env(Some(1)).await;
@@ -149,20 +336,10 @@ mod test {
         // This test ensures that two proofs, when dispatched concurrently, do not result in nonce collisions.
         // It checks that both proofs are processed and mined within a reasonable timeframe,
         // indicating that there's no interference or delay when the two are submitted simultaneously.
-        let signer = SummaSigner::new(
-            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
-            anvil.endpoint().as_str(),
-            AddressInput::Address(summa_contract.address()),
-        )
-        .await?;
-
-        let params_path = "ptau/hermez-raw-11";
-        let entry_csv = "../csv/entry_16.csv";
-        let mst = MerkleSumTree::<2, 8>::from_csv(entry_csv).unwrap();
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
 
-        let mut round_one =
-            Round::<4, 2, 8>::new(&signer, Box::new(mst.clone()), params_path, 1).unwrap();
-        let mut round_two = Round::<4, 2, 8>::new(&signer, Box::new(mst), params_path, 2).unwrap();
+        let mut round_one = initialize_round(&signer, 1);
+        let mut round_two = initialize_round(&signer, 2);
 
         // Checking block number before sending transaction of liability commitment
         let outer_provider: Provider<Http> = Provider::try_from(anvil.endpoint().as_str())?;
@@ -194,12 +371,7 @@ mod test {
     async fn test_round_features() -> Result<(), Box<dyn Error>> {
         let (anvil, cex_addr_1, cex_addr_2, _, summa_contract) = initialize_test_env(None).await;
 
-        let signer = SummaSigner::new(
-            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
-            anvil.endpoint().as_str(),
-            AddressInput::Address(summa_contract.address()),
-        )
-        .await?;
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
 
         let mut address_ownership_client =
             AddressOwnership::new(&signer, "../csv/signatures.csv").unwrap();
@@ -235,11 +407,7 @@ mod test {
     );
 
         // Initialize round
-        let params_path = "ptau/hermez-raw-11";
-        let entry_csv = "../csv/entry_16.csv";
-
-        let mst = MerkleSumTree::<2, 8>::from_csv(entry_csv).unwrap();
-        let mut round = Round::<4, 2, 8>::new(&signer, Box::new(mst), params_path, 1).unwrap();
+        let mut round = initialize_round(&signer, 1);
 
         let mut liability_commitment_logs = summa_contract
             .liabilities_commitment_submitted_filter()
@@ -279,8 +447,15 @@ mod test {
             }
         );
 
+        // The committed root should be retrievable through the signer
+        assert_eq!(
+            signer.get_committed_mst_root(1).await?,
+            liability_commitment_logs[0].mst_root
+        );
+        assert_eq!(signer.get_committed_mst_root(2).await?, U256::zero());
+
         // Test inclusion proof
-        let inclusion_proof = round.get_proof_of_inclusion(0).unwrap();
+        let inclusion_proof = round.get_proof_of_inclusion(0).await.unwrap();
 
         // Verify inclusion proof with onchain function
         let verified = summa_contract
@@ -296,4 +471,766 @@ mod test {
         drop(anvil);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_entry_from_ethereum_account() -> Result<(), Box<dyn Error>> {
+        let (_anvil, cex_addr_1, _, client, _) = initialize_test_env(None).await;
+
+        let token_1 = MockERC20::deploy(Arc::clone(&client), ())?.send().await?;
+        let token_2 = MockERC20::deploy(Arc::clone(&client), ())?.send().await?;
+
+        token_1
+            .mint(cex_addr_1, U256::from(11888))
+            .send()
+            .await?
+            .await?;
+        token_2
+            .mint(cex_addr_1, U256::from(41163))
+            .send()
+            .await?
+            .await?;
+
+        let entry = Entry::<2>::from_ethereum_account::<_, 8>(
+            Arc::clone(&client),
+            cex_addr_1,
+            &[token_1.address(), token_2.address()],
+        )
+        .await?;
+
+        assert_eq!(entry.username(), format!("{:?}", cex_addr_1));
+        assert_eq!(
+            entry.balances(),
+            &[BigUint::from(11888u32), BigUint::from(41163u32)]
+        );
+
+        // A balance that doesn't fit in 8 bytes should be rejected
+        token_1
+            .mint(cex_addr_1, U256::from(u64::MAX))
+            .send()
+            .await?
+            .await?;
+
+        let result = Entry::<2>::from_ethereum_account::<_, 8>(
+            Arc::clone(&client),
+            cex_addr_1,
+            &[token_1.address(), token_2.address()],
+        )
+        .await;
+        assert!(matches!(result, Err(OnChainFetchError::BalanceOverflow)));
+
+        // Calling `balanceOf` on an address without contract code should fail
+        let result = Entry::<1>::from_ethereum_account::<_, 8>(
+            Arc::clone(&client),
+            cex_addr_1,
+            &[cex_addr_1],
+        )
+        .await;
+        assert!(matches!(result, Err(OnChainFetchError::RpcError(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_proofs_in_range() {
+        let snapshot = initialize_snapshot();
+        let root_str = format!("{:?}", snapshot.mst.root().hash);
+        let mst_root = U256::from_str_radix(&root_str, 16).unwrap();
+
+        // Each proof is verified against the verifying key right after being generated
+        let proofs = snapshot.generate_proofs_in_range(0, 16).unwrap();
+        assert_eq!(proofs.len(), 16);
+
+        for (expected_index, (user_index, proof)) in proofs.iter().enumerate() {
+            assert_eq!(*user_index, expected_index);
+            assert_eq!(proof.as_ref().unwrap().get_public_inputs()[1], mst_root);
+        }
+
+        // Empty and out of bounds ranges should be rejected
+        assert!(snapshot.generate_proofs_in_range(8, 8).is_err());
+        assert!(snapshot.generate_proofs_in_range(0, 17).is_err());
+    }
+
+    #[test]
+    fn test_balance_statistics() {
+        let snapshot = initialize_snapshot();
+
+        assert_eq!(
+            snapshot.user_balance_percentile(0, 0.0).unwrap(),
+            BigUint::from(2087u32)
+        );
+        assert_eq!(
+            snapshot.user_balance_percentile(0, 0.5).unwrap(),
+            BigUint::from(31699u32)
+        );
+        assert_eq!(
+            snapshot.user_balance_percentile(0, 1.0).unwrap(),
+            BigUint::from(83296u32)
+        );
+
+        // Invalid currency index and percentile should be rejected
+        assert!(snapshot.user_balance_percentile(2, 0.5).is_err());
+        assert!(snapshot.user_balance_percentile(0, 1.5).is_err());
+        assert!(snapshot.user_balance_percentile(0, f64::NAN).is_err());
+
+        let histogram = snapshot.balance_histogram(0, 4);
+        let counts: Vec<usize> = histogram.iter().map(|bucket| bucket.2).collect();
+        assert_eq!(counts, vec![7, 5, 1, 3]);
+        assert_eq!(histogram[0].0, BigUint::from(2087u32));
+        assert_eq!(histogram[0].1, histogram[1].0);
+        assert!(histogram[3].1 > BigUint::from(83296u32));
+
+        assert!(snapshot.balance_histogram(0, 0).is_empty());
+        assert!(snapshot.balance_histogram(2, 4).is_empty());
+
+        // The 16 balances fall into distinct buckets
+        assert!((snapshot.balance_entropy(0).unwrap() - 4.0).abs() < 1e-9);
+        assert!(snapshot.balance_entropy(2).is_err());
+
+        assert!((snapshot.balance_correlation(0, 0).unwrap() - 1.0).abs() < 1e-9);
+        assert!((snapshot.balance_correlation(0, 1).unwrap() + 0.151479907).abs() < 1e-6);
+        assert!(snapshot.balance_correlation(0, 2).is_err());
+    }
+
+    #[test]
+    fn test_user_balance_queries() {
+        let mst = MerkleSumTree::<2, 8>::from_csv("../csv/entry_13.csv").unwrap();
+        let snapshot = Snapshot::<4, 2, 8>::new(Box::new(mst), "ptau/hermez-raw-11").unwrap();
+
+        let lo = BigUint::from(10000u32);
+        let hi = BigUint::from(20000u32);
+        assert_eq!(
+            snapshot.user_indices_by_balance_range(0, &lo, &hi).unwrap(),
+            vec![0, 2, 9, 11, 12]
+        );
+
+        // The zero entries padding the tree are not users
+        let zero = BigUint::from(0u32);
+        assert_eq!(
+            snapshot
+                .user_indices_by_balance_range(0, &zero, &zero)
+                .unwrap(),
+            Vec::<usize>::new()
+        );
+
+        assert_eq!(
+            snapshot
+                .user_count_above_balance(1, &BigUint::from(50000u32))
+                .unwrap(),
+            4
+        );
+        assert_eq!(snapshot.user_count_above_balance(1, &zero).unwrap(), 13);
+
+        assert!(snapshot.user_indices_by_balance_range(2, &lo, &hi).is_err());
+        assert!(snapshot.user_count_above_balance(2, &zero).is_err());
+    }
+
+    #[test]
+    fn test_detect_balance_anomalies() {
+        let snapshot = initialize_snapshot();
+
+        // The mean ETH balance is 34803.875
+        assert_eq!(
+            snapshot.detect_balance_anomalies(0, 2.0),
+            vec![(5, BigUint::from(83296u32)), (14, BigUint::from(79731u32))]
+        );
+        assert!(snapshot.detect_balance_anomalies(0, 3.0).is_empty());
+        assert!(snapshot.detect_balance_anomalies(2, 2.0).is_empty());
+
+        assert_eq!(snapshot.detect_zero_balance_anomaly_fraction(), 0.0);
+
+        // Half of the users have no balance, the zero entries padding the tree are not users
+        let balances = |eth: u32, usdt: u32| [BigUint::from(eth), BigUint::from(usdt)];
+        let entries = vec![
+            Entry::new("alice".to_string(), balances(0, 0)).unwrap(),
+            Entry::new("bob".to_string(), balances(10, 0)).unwrap(),
+            Entry::new("carol".to_string(), balances(0, 0)).unwrap(),
+            Entry::new("dave".to_string(), balances(0, 5)).unwrap(),
+            Entry::new("erin".to_string(), balances(0, 0)).unwrap(),
+            Entry::new("frank".to_string(), balances(20, 20)).unwrap(),
+        ];
+        let mst = MerkleSumTree::<2, 8>::from_entries(entries, vec![], false).unwrap();
+        let snapshot = Snapshot::<4, 2, 8>::new(Box::new(mst), "ptau/hermez-raw-11").unwrap();
+
+        assert_eq!(snapshot.detect_zero_balance_anomaly_fraction(), 0.5);
+        assert_eq!(
+            snapshot.detect_balance_anomalies(1, 3.0),
+            vec![(5, BigUint::from(20u32))]
+        );
+    }
+
+    #[test]
+    fn test_generate_proof_of_inclusion_deterministic() {
+        let snapshot = initialize_snapshot();
+
+        let secret_seed = [7u8; 32];
+
+        // Two proofs generated concurrently with the same seed should be identical
+        let (proof_1, proof_2) = std::thread::scope(|scope| {
+            let handle_1 =
+                scope.spawn(|| snapshot.generate_proof_of_inclusion_deterministic(3, &secret_seed));
+            let handle_2 =
+                scope.spawn(|| snapshot.generate_proof_of_inclusion_deterministic(3, &secret_seed));
+            (
+                handle_1.join().unwrap().unwrap(),
+                handle_2.join().unwrap().unwrap(),
+            )
+        });
+
+        assert_eq!(proof_1.get_proof(), proof_2.get_proof());
+        assert_eq!(proof_1.get_public_inputs(), proof_2.get_public_inputs());
+
+        // A different seed should produce a different proof for the same public inputs
+        let proof_3 = snapshot
+            .generate_proof_of_inclusion_deterministic(3, &[8u8; 32])
+            .unwrap();
+        assert_ne!(proof_1.get_proof(), proof_3.get_proof());
+        assert_eq!(proof_1.get_public_inputs(), proof_3.get_public_inputs());
+    }
+
+    #[test]
+    fn test_generate_proof_of_inclusion_cancellable() {
+        let snapshot = initialize_snapshot();
+
+        let token = CancellationToken::new();
+        assert!(snapshot
+            .generate_proof_of_inclusion_cancellable(0, &token)
+            .is_ok());
+
+        // A cancelled token should stop the generation before proving
+        token.cancel();
+        assert_eq!(
+            snapshot
+                .generate_proof_of_inclusion_cancellable(0, &token)
+                .unwrap_err(),
+            "Proof generation cancelled"
+        );
+
+        // Cancelling from another thread during the generation should not panic
+        let token = CancellationToken::new();
+        let result = std::thread::scope(|scope| {
+            let handle =
+                scope.spawn(|| snapshot.generate_proof_of_inclusion_cancellable(0, &token));
+            token.clone().cancel();
+            handle.join().unwrap()
+        });
+        assert!(result.is_ok() || result.unwrap_err() == "Proof generation cancelled");
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let cooldown = Duration::from_secs(60);
+        let mut rate_limiter = RateLimiter::new(cooldown);
+        let now = std::time::Instant::now();
+
+        assert!(rate_limiter.check(0, now).is_ok());
+        assert_eq!(
+            rate_limiter.check(0, now + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+
+        // Other users are not affected
+        assert!(rate_limiter.check(1, now + Duration::from_secs(20)).is_ok());
+
+        // Requests are allowed again after the cooldown
+        assert!(rate_limiter.check(0, now + cooldown).is_ok());
+
+        // The requests older than the cooldown are only dropped once a cooldown has passed since the last pruning
+        assert_eq!(rate_limiter.len(), 2);
+        assert!(rate_limiter.check(2, now + Duration::from_secs(90)).is_ok());
+        assert_eq!(rate_limiter.len(), 3);
+        assert!(rate_limiter.check(3, now + cooldown * 4).is_ok());
+        assert_eq!(rate_limiter.len(), 1);
+
+        // Or once the number of tracked users has doubled since the last pruning
+        let mut rate_limiter = RateLimiter::new(cooldown);
+        let later = |secs| now + Duration::from_secs(secs);
+        assert!(rate_limiter.check(0, now).is_ok());
+        for user_index in 1..512 {
+            assert!(rate_limiter.check(user_index, later(50)).is_ok());
+        }
+        assert!(rate_limiter.check(512, later(60)).is_ok());
+        assert_eq!(rate_limiter.len(), 512);
+
+        // Less than a cooldown after the last pruning, the 1024th tracked user drops the requests made at 50s
+        for user_index in 1000..1511 {
+            assert!(rate_limiter.check(user_index, later(115)).is_ok());
+        }
+        assert_eq!(rate_limiter.len(), 1023);
+        assert!(rate_limiter.check(1511, later(115)).is_ok());
+        assert_eq!(rate_limiter.len(), 513);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_round() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let round = initialize_round(&signer, 1).with_rate_limiter(Duration::from_secs(60));
+
+        assert!(round.get_proof_of_inclusion(0).await.is_ok());
+
+        match round.get_proof_of_inclusion(0).await {
+            Err(RoundError::RateLimited {
+                user_index,
+                retry_after,
+            }) => {
+                assert_eq!(user_index, 0);
+                assert!(retry_after <= Duration::from_secs(60));
+            }
+            _ => panic!("The second request should be rate limited"),
+        }
+
+        assert!(round.get_proof_of_inclusion(1).await.is_ok());
+
+        // An invalid user index fails even when not rate limited
+        assert_eq!(
+            round.get_proof_of_inclusion(16).await.unwrap_err(),
+            RoundError::ProofGeneration("Failed to generate the merkle proof")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_commitment_for_contract_abi() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let round = initialize_round(&signer, 1);
+
+        let calldata = round.get_commitment_for_contract_abi();
+        assert_eq!(calldata[..4], SubmitCommitmentCall::selector());
+
+        let tokens = decode(
+            &[
+                ParamType::Uint(256),
+                ParamType::Array(Box::new(ParamType::Uint(256))),
+                ParamType::Array(Box::new(ParamType::Tuple(vec![
+                    ParamType::String,
+                    ParamType::String,
+                ]))),
+                ParamType::Uint(256),
+            ],
+            &calldata[4..],
+        )?;
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Uint(
+                    "0x18d6ab953235a811edffa4cead74ea045e7cd2085771a2269d59dca054c955b1"
+                        .parse()
+                        .unwrap()
+                ),
+                Token::Array(vec![
+                    Token::Uint(U256::from(556862)),
+                    Token::Uint(U256::from(556862))
+                ]),
+                Token::Array(vec![
+                    Token::Tuple(vec![
+                        Token::String("ETH".to_string()),
+                        Token::String("ETH".to_string())
+                    ]),
+                    Token::Tuple(vec![
+                        Token::String("USDT".to_string()),
+                        Token::String("ETH".to_string())
+                    ]),
+                ]),
+                Token::Uint(U256::from(1)),
+            ]
+        );
+
+        // The root hash is encoded as a big-endian word
+        assert_eq!(
+            calldata[4..36],
+            hex::decode("18d6ab953235a811edffa4cead74ea045e7cd2085771a2269d59dca054c955b1")?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_proof_of_inclusion_async() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let round = initialize_round(&signer, 1);
+
+        // Both proofs are generated on the blocking thread pool while the runtime stays free
+        let (first_proof, second_proof) = tokio::join!(
+            round.get_proof_of_inclusion(0),
+            round.get_proof_of_inclusion(1)
+        );
+
+        let snapshot = round.get_snapshot();
+        assert_eq!(
+            first_proof.unwrap().get_public_inputs(),
+            snapshot
+                .generate_proof_of_inclusion(0)
+                .unwrap()
+                .get_public_inputs()
+        );
+        assert_eq!(
+            second_proof.unwrap().get_public_inputs(),
+            snapshot
+                .generate_proof_of_inclusion(1)
+                .unwrap()
+                .get_public_inputs()
+        );
+
+        assert_eq!(
+            round.get_proof_of_inclusion(16).await.unwrap_err(),
+            RoundError::ProofGeneration("Failed to generate the merkle proof")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_proof_with_claimed_values() {
+        let snapshot = initialize_snapshot();
+        let entry = snapshot.mst.get_entry(0).clone();
+
+        let claimed_username = big_uint_to_fp(entry.username_as_big_uint());
+        let claimed_balances = [
+            big_uint_to_fp(&entry.balances()[0]),
+            big_uint_to_fp(&entry.balances()[1]),
+        ];
+
+        let proof = snapshot
+            .generate_proof_with_claimed_values(0, claimed_username, &claimed_balances)
+            .unwrap();
+        assert_eq!(
+            proof.get_public_inputs(),
+            snapshot
+                .generate_proof_of_inclusion(0)
+                .unwrap()
+                .get_public_inputs()
+        );
+
+        // A claimed balance that differs from the one in the tree should be rejected
+        let wrong_balances = [claimed_balances[0], claimed_balances[0]];
+        assert_eq!(
+            snapshot
+                .generate_proof_with_claimed_values(0, claimed_username, &wrong_balances)
+                .unwrap_err(),
+            RoundError::ClaimedValueMismatch
+        );
+
+        // The claimed values of a user don't match the leaf of another user
+        assert_eq!(
+            snapshot
+                .generate_proof_with_claimed_values(1, claimed_username, &claimed_balances)
+                .unwrap_err(),
+            RoundError::ClaimedValueMismatch
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compliance_auditor() {
+        let snapshot = initialize_snapshot();
+
+        let mut auditor = ComplianceAuditor::new(snapshot);
+        let caller_ip: std::net::IpAddr = "127.0.0.1".parse().unwrap();
+
+        let proof = auditor
+            .get_proof_of_inclusion(0, Some(caller_ip))
+            .await
+            .unwrap();
+        assert_eq!(
+            auditor.get_proof_of_inclusion(16, None).await.unwrap_err(),
+            RoundError::ProofGeneration("Failed to generate the merkle proof")
+        );
+
+        let entries = auditor.get_log().get_entries();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0].user_index, 0);
+        assert_eq!(entries[0].caller_ip, Some(caller_ip));
+        assert_eq!(entries[0].proof_hash, keccak256(proof.get_proof()));
+        assert!(entries[0].success);
+
+        assert_eq!(entries[1].user_index, 16);
+        assert_eq!(entries[1].caller_ip, None);
+        assert_eq!(entries[1].proof_hash, [0u8; 32]);
+        assert!(!entries[1].success);
+
+        let mut csv = Vec::new();
+        auditor.get_log().export_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "timestamp,caller_ip,user_index,proof_hash,success"
+        );
+        assert!(lines[1].contains(",127.0.0.1,0,0x"));
+        assert!(lines[1].ends_with(",true"));
+        assert!(lines[2].ends_with(&format!(",,16,0x{},false", "0".repeat(64))));
+    }
+
+    #[test]
+    fn test_verification_key_wrapper() {
+        let snapshot = initialize_snapshot();
+
+        let vk = snapshot.get_verification_key();
+        let encoded_vk = vk.to_base64();
+
+        let decoded_vk = VerificationKeyWrapper::<4, 2, 8>::from_base64(&encoded_vk).unwrap();
+        assert_eq!(decoded_vk.to_base64(), encoded_vk);
+        assert_eq!(
+            decoded_vk.0.transcript_repr(),
+            snapshot.get_verification_key().0.transcript_repr()
+        );
+
+        // The key should survive a JSON round trip as a base64 string
+        let json = serde_json::to_string(&vk).unwrap();
+        assert_eq!(json, format!("\"{}\"", encoded_vk));
+        let parsed_vk: VerificationKeyWrapper<4, 2, 8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed_vk.to_base64(), encoded_vk);
+
+        assert!(matches!(
+            VerificationKeyWrapper::<4, 2, 8>::from_base64("not base64!"),
+            Err(VkError::Base64(_))
+        ));
+        assert!(matches!(
+            VerificationKeyWrapper::<4, 2, 8>::from_base64(&base64::encode([1u8; 8])),
+            Err(VkError::Io(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_round_summary() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let signer = initialize_signer(&anvil, summa_contract.address()).await;
+
+        let round = initialize_round(&signer, 1);
+
+        let summary = round.get_summary();
+        assert_eq!(summary.timestamp, 1);
+        assert_eq!(
+            summary.mst_root,
+            U256::from_str_radix(&format!("{:?}", round.get_snapshot().mst.root().hash), 16)
+                .unwrap()
+        );
+        assert_eq!(
+            summary.root_balances,
+            vec![U256::from(556862), U256::from(556862)]
+        );
+
+        // The verifying key is distributed as a base64 field of the JSON summary
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["verifying_key"], summary.verifying_key.to_base64());
+
+        let parsed_summary: RoundSummary<4, 2, 8> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed_summary.timestamp, summary.timestamp);
+        assert_eq!(parsed_summary.mst_root, summary.mst_root);
+        assert_eq!(parsed_summary.root_balances, summary.root_balances);
+        assert_eq!(
+            parsed_summary.verifying_key.0.transcript_repr(),
+            round
+                .get_snapshot()
+                .get_verification_key()
+                .0
+                .transcript_repr()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "grpc")]
+    #[tokio::test]
+    async fn test_grpc_service() {
+        use crate::apis::grpc::{proto::InclusionProofRequest, SummaProver, SummaProverService};
+
+        let snapshot = initialize_snapshot();
+        let expected_proof = snapshot.generate_proof_of_inclusion(0).unwrap();
+
+        let service = SummaProverService::new(Arc::new(snapshot));
+        let response = service
+            .get_inclusion_proof(tonic::Request::new(InclusionProofRequest { user_index: 0 }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(response.proof_calldata, expected_proof.get_proof().to_vec());
+        // The public inputs are 32-byte big-endian words
+        let public_inputs: Vec<U256> = response
+            .public_inputs
+            .iter()
+            .map(|input| {
+                assert_eq!(input.len(), 32);
+                U256::from_big_endian(input)
+            })
+            .collect();
+        assert_eq!(&public_inputs, expected_proof.get_public_inputs());
+
+        // The tree has 16 users
+        let status = service
+            .get_inclusion_proof(tonic::Request::new(InclusionProofRequest {
+                user_index: 16,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_hardware_signer() -> Result<(), Box<dyn Error>> {
+        let (anvil, _, _, _, summa_contract) = initialize_test_env(None).await;
+
+        let hardware_signer = |confirm: bool, prompts: &Arc<AtomicUsize>| {
+            SummaHardwareSigner::new(MockHardwareWallet {
+                wallet: LocalWallet::from_str(SIGNER_KEY).unwrap(),
+                confirm,
+                prompts: Arc::clone(prompts),
+            })
+        };
+
+        // The commitment is sent once the user confirms it on the device
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let signer = SummaSigner::with_signer(
+            hardware_signer(true, &prompts),
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mut round = initialize_round(&signer, 1);
+        round.dispatch_commitment().await?;
+
+        assert_eq!(prompts.load(Ordering::SeqCst), 1);
+        assert_ne!(signer.get_committed_mst_root(1).await?, U256::zero());
+
+        // Nothing is sent if the user rejects the commitment on the device
+        let prompts = Arc::new(AtomicUsize::new(0));
+        let signer = SummaSigner::with_signer(
+            hardware_signer(false, &prompts),
+            anvil.endpoint().as_str(),
+            AddressInput::Address(summa_contract.address()),
+        )
+        .await?;
+
+        let mut round = initialize_round(&signer, 2);
+        assert!(round.dispatch_commitment().await.is_err());
+
+        assert_eq!(prompts.load(Ordering::SeqCst), 1);
+        assert_eq!(signer.get_committed_mst_root(2).await?, U256::zero());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimistic_proof_cache() {
+        let snapshot = initialize_snapshot();
+
+        // The log is removed along with its directory, which is unique to this run
+        let log_dir = tempfile::tempdir().unwrap();
+        let log_path = log_dir.path().join("optimistic_proof_cache.wal");
+
+        // Resuming requires an existing log
+        assert!(matches!(
+            OptimisticProofCache::resume_from_log(&log_path),
+            Err(CacheError::Io(_))
+        ));
+
+        let mut cache = OptimisticProofCache::new(&log_path).unwrap();
+        assert!(cache.is_empty());
+
+        assert_eq!(
+            snapshot
+                .generate_batch_proofs_of_inclusion(0, 2, &mut cache)
+                .unwrap(),
+            vec![(0, Ok(())), (1, Ok(()))]
+        );
+        assert_eq!(cache.len(), 2);
+
+        // Simulate a crash in the middle of writing the next record
+        let mut log = OpenOptions::new().append(true).open(&log_path).unwrap();
+        log.write_all(&[2, 0, 100, 0, 0, 0, b'{']).unwrap();
+
+        let mut resumed_cache = OptimisticProofCache::resume_from_log(&log_path).unwrap();
+        assert_eq!(resumed_cache.len(), 2);
+        for user_index in 0..2 {
+            assert_eq!(
+                resumed_cache.get(user_index).unwrap().get_proof(),
+                cache.get(user_index).unwrap().get_proof()
+            );
+        }
+
+        // Only the missing proofs are generated, and they are appended after the last complete record
+        assert_eq!(
+            snapshot
+                .generate_batch_proofs_of_inclusion(0, 4, &mut resumed_cache)
+                .unwrap(),
+            vec![(2, Ok(())), (3, Ok(()))]
+        );
+        assert_eq!(
+            resumed_cache.get(0).unwrap().get_proof(),
+            cache.get(0).unwrap().get_proof()
+        );
+
+        let replayed_cache = OptimisticProofCache::new(&log_path).unwrap();
+        assert_eq!(replayed_cache.len(), 4);
+        assert_eq!(
+            replayed_cache.get(3).unwrap().get_public_inputs(),
+            snapshot
+                .generate_proof_of_inclusion(3)
+                .unwrap()
+                .get_public_inputs()
+        );
+
+        assert!(matches!(
+            snapshot.generate_batch_proofs_of_inclusion(0, 17, &mut resumed_cache),
+            Err(CacheError::ProofGeneration("Invalid user index range"))
+        ));
+
+        // A user whose proof fails doesn't stop the batch, the proofs of the other users are still logged
+        let faulty_snapshot = Snapshot::<4, 2, 8>::new(
+            Box::new(HiddenLeafTree {
+                mst: initialize_mst(),
+                hidden_index: 5,
+            }),
+            "ptau/hermez-raw-11",
+        )
+        .unwrap();
+        assert_eq!(
+            faulty_snapshot
+                .generate_batch_proofs_of_inclusion(4, 7, &mut resumed_cache)
+                .unwrap(),
+            vec![
+                (4, Ok(())),
+                (
+                    5,
+                    Err(RoundError::ProofGeneration(
+                        "Failed to generate the merkle proof"
+                    ))
+                ),
+                (6, Ok(())),
+            ]
+        );
+        assert_eq!(OptimisticProofCache::new(&log_path).unwrap().len(), 6);
+        assert!(!resumed_cache.contains(5));
+    }
+
+    #[test]
+    fn test_smoke_test_first_n_users() {
+        let snapshot = initialize_snapshot();
+
+        assert_eq!(snapshot.smoke_test_first_n_users(10), Ok(()));
+
+        // The tree only has 16 users
+        assert_eq!(
+            snapshot.smoke_test_first_n_users(17),
+            Err(SmokeTestError::ProofGeneration {
+                user_index: 16,
+                message: "Failed to generate the merkle proof",
+            })
+        );
+    }
 }
@@ -7,13 +7,15 @@ use summa_solvency::{
         utils::{full_prover, full_verifier, generate_setup_artifacts},
         WithInstances,
     },
-    merkle_sum_tree::{MerkleSumTree, Tree},
+    merkle_sum_tree::{Entry, MerkleSumTree, SparseMerkleSumTree, Tree},
 };
 
 const SAMPLE_SIZE: usize = 10;
 const LEVELS: usize = 20;
 const N_CURRENCIES: usize = 1;
 const N_BYTES: usize = 8;
+const N_NON_ZERO_ENTRIES: usize = 1000;
+const N_AUDITED_LEAVES: usize = 1000;
 
 fn build_mstree(_c: &mut Criterion) {
     let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
@@ -49,6 +51,87 @@ fn build_sorted_mstree(_c: &mut Criterion) {
     });
 }
 
+fn build_sparse_mstree(_c: &mut Criterion) {
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    let csv_file = format!("benches/csv/{}_entry_2_{}.csv", N_CURRENCIES, LEVELS);
+    let merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv(&csv_file).unwrap();
+
+    // Spread the non-zero entries evenly among the leaves of the tree
+    let total_size = 2usize.pow(LEVELS as u32);
+    let step = total_size / N_NON_ZERO_ENTRIES;
+    let non_zero_entries: Vec<(usize, Entry<N_CURRENCIES>)> = (0..N_NON_ZERO_ENTRIES)
+        .map(|i| (i * step, merkle_sum_tree.entries()[i].clone()))
+        .collect();
+
+    let mut entries = vec![Entry::<N_CURRENCIES>::zero_entry(); total_size];
+    for (index, entry) in &non_zero_entries {
+        entries[*index] = entry.clone();
+    }
+
+    let bench_name = format!(
+        "build dense Merkle sum tree for 2 power of {} entries with {} non-zero entries",
+        LEVELS, N_NON_ZERO_ENTRIES
+    );
+
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries.clone(), vec![], false)
+                .unwrap();
+        })
+    });
+
+    let bench_name = format!(
+        "build sparse Merkle sum tree for 2 power of {} entries with {} non-zero entries",
+        LEVELS, N_NON_ZERO_ENTRIES
+    );
+
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+                &non_zero_entries,
+                total_size,
+                vec![],
+            )
+            .unwrap();
+        })
+    });
+}
+
+fn audit_leaf_range_mstree(_c: &mut Criterion) {
+    let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
+
+    let csv_file = format!("benches/csv/{}_entry_2_{}.csv", N_CURRENCIES, LEVELS);
+    let merkle_sum_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv(&csv_file).unwrap();
+
+    let (start, end) = (N_AUDITED_LEAVES, 2 * N_AUDITED_LEAVES);
+
+    let bench_name = format!(
+        "audit {} leaves with a single traversal of a Merkle sum tree of 2 power of {} entries",
+        N_AUDITED_LEAVES, LEVELS
+    );
+
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            merkle_sum_tree.audit_leaf_range(start, end).unwrap();
+        })
+    });
+
+    let bench_name = format!(
+        "audit {} leaves with one Merkle proof per leaf of a Merkle sum tree of 2 power of {} entries",
+        N_AUDITED_LEAVES, LEVELS
+    );
+
+    criterion.bench_function(&bench_name, |b| {
+        b.iter(|| {
+            for index in start..end {
+                let proof = merkle_sum_tree.generate_proof(index).unwrap();
+                merkle_sum_tree.verify_proof(&proof).unwrap();
+            }
+        })
+    });
+}
+
 fn verification_key_gen_mst_inclusion_circuit(_c: &mut Criterion) {
     let mut criterion = Criterion::default().sample_size(SAMPLE_SIZE);
 
@@ -153,6 +236,8 @@ criterion_group!(
     benches,
     build_mstree,
     build_sorted_mstree,
+    build_sparse_mstree,
+    audit_leaf_range_mstree,
     verification_key_gen_mst_inclusion_circuit,
     proving_key_gen_mst_inclusion_circuit,
     generate_zk_proof_mst_inclusion_circuit,
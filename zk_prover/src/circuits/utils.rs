@@ -135,10 +135,23 @@ pub fn gen_proof_solidity_calldata<C: Circuit<Fp> + WithInstances>(
     params: &ParamsKZG<Bn256>,
     pk: &ProvingKey<G1Affine>,
     circuit: C,
+) -> (Bytes, Vec<U256>) {
+    gen_proof_solidity_calldata_with_rng(params, pk, circuit, OsRng)
+}
+
+/// Generate the proof Solidity calldata for a circuit, drawing the blinding factors of the proof from `rng`.
+///
+/// Given the same circuit and an `rng` seeded in the same way, the generated proof is bit-identical.
+/// The zero-knowledge property of the proof relies on the seed of `rng` being kept secret.
+pub fn gen_proof_solidity_calldata_with_rng<C: Circuit<Fp> + WithInstances>(
+    params: &ParamsKZG<Bn256>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    rng: impl RngCore,
 ) -> (Bytes, Vec<U256>) {
     let instances_clone = circuit.instances().clone();
     let pf_time = start_timer!(|| "Creating proof");
-    let proof = create_proof_checked(params, pk, circuit, &instances_clone[0], OsRng);
+    let proof = create_proof_checked(params, pk, circuit, &instances_clone[0], rng);
     end_timer!(pf_time);
 
     let calldata = encode_calldata(None, &proof, &instances_clone[0]);
@@ -195,6 +208,40 @@ fn create_proof_checked(
     proof
 }
 
+/// Verifies off-chain a proof generated by `gen_proof_solidity_calldata`, as the Solidity verifier would.
+///
+/// Returns `false` if the proof is invalid or if a public input is not a field element.
+pub fn verify_proof_solidity_calldata(
+    params: &ParamsKZG<Bn256>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &Bytes,
+    public_inputs: &[U256],
+) -> bool {
+    let instances: Option<Vec<Fp>> = public_inputs
+        .iter()
+        .map(|input| {
+            let mut bytes = [0u8; 32];
+            input.to_little_endian(&mut bytes);
+            Option::from(Fp::from_repr(bytes))
+        })
+        .collect();
+
+    let instances = match instances {
+        Some(instances) => instances,
+        None => return false,
+    };
+
+    let mut transcript = Keccak256Transcript::new(&proof[..]);
+    verify_proof::<_, VerifierSHPLONK<_>, _, _, SingleStrategy<_>>(
+        params,
+        vk,
+        SingleStrategy::new(params),
+        &[&[&instances[..]]],
+        &mut transcript,
+    )
+    .is_ok()
+}
+
 /// Converts a field element to a Solidity calldata
 pub fn field_element_to_solidity_calldata(field_element: Fp) -> U256 {
     let bytes = field_element.to_repr();
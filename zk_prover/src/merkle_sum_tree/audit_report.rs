@@ -0,0 +1,67 @@
+use crate::merkle_sum_tree::utils::fp_to_big_uint;
+use crate::merkle_sum_tree::{MerkleSumTree, Tree};
+use num_bigint::BigUint;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A publishable report of the verification of the Merkle proofs of all the leaves of a Merkle Sum Tree.
+///
+/// Fields:
+/// * `timestamp`: The timestamp of the round the tree was built for
+/// * `root_hash`: The little-endian byte representation of the root hash
+/// * `total_leaves`: The number of leaves of the tree, including the padding zero entries
+/// * `verified_count`: The number of leaves whose Merkle proof verified
+/// * `failed_indices`: The indices of the leaves whose Merkle proof couldn't be generated or didn't verify
+/// * `tree_depth`: The depth of the tree
+/// * `total_balance_per_currency`: The root balances of the tree
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MerkleAuditReport {
+    pub timestamp: u64,
+    pub root_hash: [u8; 32],
+    pub total_leaves: usize,
+    pub verified_count: usize,
+    pub failed_indices: Vec<usize>,
+    pub tree_depth: usize,
+    pub total_balance_per_currency: Vec<BigUint>,
+}
+
+impl MerkleAuditReport {
+    /// Returns `true` if the Merkle proof of every leaf verified
+    pub fn is_fully_valid(&self) -> bool {
+        self.failed_indices.is_empty()
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Generates and verifies the Merkle proof of every leaf of the tree and collects the results in a `MerkleAuditReport`
+    pub fn generate_audit_report(&self, timestamp: u64) -> MerkleAuditReport
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let total_leaves = self.leaves().len();
+
+        let failed_indices: Vec<usize> = (0..total_leaves)
+            .into_par_iter()
+            .filter(|index| match self.generate_proof(*index) {
+                Ok(proof) => self.verify_proof(&proof).is_err(),
+                Err(_) => true,
+            })
+            .collect();
+
+        MerkleAuditReport {
+            timestamp,
+            root_hash: self.root().hash.to_bytes(),
+            total_leaves,
+            verified_count: total_leaves - failed_indices.len(),
+            failed_indices,
+            tree_depth: *self.depth(),
+            total_balance_per_currency: self
+                .root()
+                .balances
+                .iter()
+                .map(|balance| fp_to_big_uint(*balance))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::merkle_sum_tree::{MerkleProof, Node, Tree};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A compact form of a MerkleProof for long-term storage.
+///
+/// Fields:
+/// * `leaf_index`: The index of the leaf of the entry, from which the entry and the hash preimages are looked up again
+/// * `root_hash`: The little-endian byte representation of the root hash
+/// * `sibling_hashes`: The little-endian byte representation of the hash of the sibling node at each level, starting from the leaf level
+/// * `sibling_sums`: The little-endian byte representation of the balances of the sibling node at each level, `N_CURRENCIES` per level
+/// * `path_indices`: The position of the node on the path at each level, 0 if it is the left child and 1 if it is the right child
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompressedMerkleProof {
+    pub leaf_index: usize,
+    pub root_hash: [u8; 32],
+    pub sibling_hashes: Vec<[u8; 32]>,
+    pub sibling_sums: Vec<[u8; 32]>,
+    pub path_indices: Vec<u8>,
+}
+
+impl<const N_CURRENCIES: usize> MerkleProof<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Compresses the proof by replacing the entry with its leaf index and the sibling hash preimages with the sibling nodes
+    pub fn compress(&self) -> CompressedMerkleProof {
+        let sibling_nodes: Vec<Node<N_CURRENCIES>> = std::iter::once(
+            Node::leaf_node_from_preimage(&self.sibling_leaf_node_hash_preimage),
+        )
+        .chain(
+            self.sibling_middle_node_hash_preimages
+                .iter()
+                .map(Node::middle_node_from_preimage),
+        )
+        .collect();
+
+        let path_indices: Vec<u8> = self
+            .path_indices
+            .iter()
+            .map(|path_index| path_index.to_bytes()[0])
+            .collect();
+
+        CompressedMerkleProof {
+            leaf_index: path_indices
+                .iter()
+                .rev()
+                .fold(0, |index, path_index| 2 * index + *path_index as usize),
+            root_hash: self.root.hash.to_bytes(),
+            sibling_hashes: sibling_nodes
+                .iter()
+                .map(|node| node.hash.to_bytes())
+                .collect(),
+            sibling_sums: sibling_nodes
+                .iter()
+                .flat_map(|node| node.balances.iter().map(|balance| balance.to_bytes()))
+                .collect(),
+            path_indices,
+        }
+    }
+}
+
+impl CompressedMerkleProof {
+    /// Restores the full MerkleProof from the tree the proof was generated from.
+    ///
+    /// Returns an error if the tree no longer yields the compressed proof, for example because the entry or one of the siblings changed.
+    pub fn restore<const N_CURRENCIES: usize, T: Tree<N_CURRENCIES> + ?Sized>(
+        &self,
+        tree: &T,
+    ) -> Result<MerkleProof<N_CURRENCIES>, Box<dyn Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let proof = tree.generate_proof(self.leaf_index)?;
+
+        if proof.compress() != *self {
+            return Err(Box::from("The tree doesn't match the compressed proof"));
+        }
+
+        Ok(proof)
+    }
+}
@@ -1,10 +1,118 @@
 use crate::merkle_sum_tree::utils::big_intify_username;
 use crate::merkle_sum_tree::Node;
+use ethers::{contract::abigen, providers::Middleware, types::Address, utils::keccak256};
 use num_bigint::BigUint;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+// The only call of the ERC-20 interface needed to fetch the balances of an account
+abigen!(
+    IERC20,
+    r#"[
+        function balanceOf(address account) external view returns (uint256)
+    ]"#,
+);
+
+/// The reason why a row of an exchange export couldn't be parsed into an Entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntryParseError {
+    /// The header of the export is not `User ID,Asset,Free,Locked`
+    InvalidHeader(String),
+    /// The row has no value in the given column
+    MissingColumn(&'static str),
+    /// The asset of the row is not in the asset map or in the decimals map
+    UnknownAsset(String),
+    /// The amount is not a non-negative decimal number with at most the decimals of its asset
+    InvalidAmount(String),
+    /// The entry built from the row is invalid
+    InvalidEntry(&'static str),
+}
+
+impl fmt::Display for EntryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryParseError::InvalidHeader(header) => write!(f, "Invalid header {}", header),
+            EntryParseError::MissingColumn(column) => write!(f, "Missing column {}", column),
+            EntryParseError::UnknownAsset(asset) => write!(f, "Unknown asset {}", asset),
+            EntryParseError::InvalidAmount(amount) => write!(f, "Invalid amount {}", amount),
+            EntryParseError::InvalidEntry(message) => write!(f, "Invalid entry: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for EntryParseError {}
+
+/// The reason why an Entry couldn't be built from the on-chain balances of an account.
+#[derive(Debug)]
+pub enum OnChainFetchError {
+    /// The `balanceOf` call to a token contract failed
+    RpcError(String),
+    /// A fetched balance doesn't fit in `N_BYTES` bytes
+    BalanceOverflow,
+    /// The entry built from the balances is invalid
+    InvalidEntry(&'static str),
+}
+
+impl fmt::Display for OnChainFetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnChainFetchError::RpcError(message) => {
+                write!(f, "Failed to fetch the balance: {}", message)
+            }
+            OnChainFetchError::BalanceOverflow => {
+                write!(f, "The balance exceeds the range of the Merkle Sum Tree")
+            }
+            OnChainFetchError::InvalidEntry(message) => write!(f, "Invalid entry: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for OnChainFetchError {}
+
+/// The header of a Binance balance export
+const BINANCE_EXPORT_HEADER: [&str; 4] = ["User ID", "Asset", "Free", "Locked"];
+
+/// Parses a decimal amount with at most `decimals` significant fractional digits into an integer amount in the smallest unit of its asset,
+/// e.g. `0.00120000` with 8 decimals is `120000`.
+fn parse_decimal_amount(amount: &str, decimals: u32) -> Result<BigUint, EntryParseError> {
+    let invalid_amount = || EntryParseError::InvalidAmount(amount.to_string());
+    let is_number = |digits: &str| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+
+    let (integer_part, fractional_part) = match amount.trim().split_once('.') {
+        Some((integer_part, fractional_part)) if is_number(fractional_part) => {
+            (integer_part, fractional_part)
+        }
+        Some(_) => return Err(invalid_amount()),
+        None => (amount.trim(), ""),
+    };
+
+    // The trailing zeros don't change the amount, so they may exceed the decimals of the asset
+    let fractional_part = fractional_part.trim_end_matches('0');
+    if !is_number(integer_part) || fractional_part.len() > decimals as usize {
+        return Err(invalid_amount());
+    }
+
+    let smallest_units = format!(
+        "{}{:0<width$}",
+        integer_part,
+        fractional_part,
+        width = decimals as usize
+    );
+    BigUint::parse_bytes(smallest_units.as_bytes(), 10).ok_or_else(invalid_amount)
+}
 
 /// An entry in the Merkle Sum Tree from the database of the CEX.
 /// It contains the username and the balances of the user.
-#[derive(Clone, Debug, std::cmp::PartialEq)]
+///
+/// Entries are compared and ordered by `username_as_big_uint` only, so two entries of the same user with different balances are equal.
+/// The order is the numeric order of the big-endian username bytes: a shorter username comes before a longer one.
+/// It is not the string order used by `MerkleSumTree::from_csv_sorted` and `MerkleSumTree::generate_null_proof`, e.g. `"b" < "aa"` as entries but `"aa" < "b"` as strings,
+/// so entries sorted with `sort()` don't make a sorted tree.
+/// Use `compute_leaf` to compare the balances as well.
+#[derive(Clone, Debug)]
 pub struct Entry<const N_CURRENCIES: usize> {
     username_as_big_uint: BigUint,
     balances: [BigUint; N_CURRENCIES],
@@ -31,6 +139,15 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
         }
     }
 
+    /// Returns `true` if the entry is a zero entry, as used to pad the Merkle Sum Tree
+    pub fn is_zero_entry(&self) -> bool {
+        self.username_as_big_uint == BigUint::from(0u32)
+            && self
+                .balances
+                .iter()
+                .all(|balance| *balance == BigUint::from(0u32))
+    }
+
     pub fn compute_leaf(&self) -> Node<N_CURRENCIES>
     where
         [usize; N_CURRENCIES + 1]: Sized,
@@ -52,6 +169,111 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
         Node::leaf(&self.username_as_big_uint, updated_balances)
     }
 
+    /// Returns the canonical 64 bytes representation of the entry, for use as a database key.
+    ///
+    /// Unlike `Eq` and `Hash`, which only look at the username, the representation commits to the balances:
+    /// use it as a map key to tell apart two entries of the same user with different balances.
+    ///
+    /// * The first 32 bytes are `username_as_big_uint` in little-endian, padded with zeros.
+    /// * If `N_CURRENCIES == 1`, the last 32 bytes are the balance in little-endian, padded with zeros.
+    /// * Otherwise, the last 32 bytes are `keccak256(balance[0] || ... || balance[N_CURRENCIES - 1])`, where each balance is encoded as 32 bytes in little-endian, padded with zeros.
+    ///
+    /// Values longer than 32 bytes are truncated to their 32 least significant bytes. They can't be part of a Merkle Sum Tree anyway, as they don't fit in a field element.
+    pub fn to_leaf_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&to_le_bytes_32(&self.username_as_big_uint));
+
+        if N_CURRENCIES == 1 {
+            bytes[32..].copy_from_slice(&to_le_bytes_32(&self.balances[0]));
+        } else {
+            let balances_bytes: Vec<u8> = self.balances.iter().flat_map(to_le_bytes_32).collect();
+            bytes[32..].copy_from_slice(&keccak256(balances_bytes));
+        }
+
+        bytes
+    }
+
+    /// Checks that `header` is the header of a Binance balance export, `User ID,Asset,Free,Locked`.
+    ///
+    /// The rows don't carry the header, so it is to be checked once on the header of the reader before parsing the rows with `from_binance_export_row`.
+    pub fn check_binance_export_header(header: &csv::StringRecord) -> Result<(), EntryParseError> {
+        if header.iter().map(str::trim).ne(BINANCE_EXPORT_HEADER) {
+            return Err(EntryParseError::InvalidHeader(
+                header.iter().collect::<Vec<_>>().join(","),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Parses a row of a Binance balance export into an entry.
+    ///
+    /// The export has the header `User ID,Asset,Free,Locked`, checked by `check_binance_export_header`, and one row per user and asset:
+    ///
+    /// `User ID,Asset,Free,Locked`
+    ///
+    /// `10485761,BTC,0.00120000,0.00050000`
+    ///
+    /// The amounts are decimal numbers of the asset, as `0.00120000` BTC. They are converted to integers in the smallest unit of the asset, using the number of decimals of the asset in `asset_decimals`.
+    /// The balance of the currency mapped to the asset of the row by `asset_map` is `Free + Locked`, all the other balances are 0. The entries of the rows of a same user are to be summed by the caller.
+    pub fn from_binance_export_row(
+        row: &csv::StringRecord,
+        asset_map: &HashMap<String, usize>,
+        asset_decimals: &HashMap<String, u32>,
+    ) -> Result<Self, EntryParseError> {
+        let user_id = row
+            .get(0)
+            .ok_or(EntryParseError::MissingColumn("User ID"))?;
+        let asset = row.get(1).ok_or(EntryParseError::MissingColumn("Asset"))?;
+        let free = row.get(2).ok_or(EntryParseError::MissingColumn("Free"))?;
+        let locked = row.get(3).ok_or(EntryParseError::MissingColumn("Locked"))?;
+
+        let currency_index = *asset_map
+            .get(asset.trim())
+            .filter(|index| **index < N_CURRENCIES)
+            .ok_or_else(|| EntryParseError::UnknownAsset(asset.to_string()))?;
+        let decimals = *asset_decimals
+            .get(asset.trim())
+            .ok_or_else(|| EntryParseError::UnknownAsset(asset.to_string()))?;
+
+        let mut balances: [BigUint; N_CURRENCIES] = std::array::from_fn(|_| BigUint::from(0u32));
+        balances[currency_index] =
+            parse_decimal_amount(free, decimals)? + parse_decimal_amount(locked, decimals)?;
+
+        Entry::new(user_id.trim().to_string(), balances).map_err(EntryParseError::InvalidEntry)
+    }
+
+    /// Builds the entry of `user_address` from its balances of the ERC-20 tokens at `token_addresses`, fetched on-chain with `balanceOf`.
+    ///
+    /// The username of the entry is the `0x`-prefixed hex representation of `user_address`.
+    /// The balances are ordered as `token_addresses` and each of them must fit in `N_BYTES` bytes.
+    pub async fn from_ethereum_account<M: Middleware, const N_BYTES: usize>(
+        client: Arc<M>,
+        user_address: Address,
+        token_addresses: &[Address; N_CURRENCIES],
+    ) -> Result<Self, OnChainFetchError> {
+        let mut balances: [BigUint; N_CURRENCIES] = std::array::from_fn(|_| BigUint::from(0u32));
+
+        for (balance, token_address) in balances.iter_mut().zip(token_addresses.iter()) {
+            let token = IERC20::new(*token_address, Arc::clone(&client));
+            let fetched_balance = token
+                .balance_of(user_address)
+                .call()
+                .await
+                .map_err(|e| OnChainFetchError::RpcError(e.to_string()))?;
+
+            let mut balance_bytes = [0u8; 32];
+            fetched_balance.to_little_endian(&mut balance_bytes);
+            *balance = BigUint::from_bytes_le(&balance_bytes);
+
+            if balance.bits() > (N_BYTES * 8) as u64 {
+                return Err(OnChainFetchError::BalanceOverflow);
+            }
+        }
+
+        Entry::new(format!("{:?}", user_address), balances).map_err(OnChainFetchError::InvalidEntry)
+    }
+
     pub fn balances(&self) -> &[BigUint; N_CURRENCIES] {
         &self.balances
     }
@@ -63,4 +285,87 @@ impl<const N_CURRENCIES: usize> Entry<N_CURRENCIES> {
     pub fn username(&self) -> &str {
         &self.username
     }
+
+    /// Returns an entry with username `{prefix}_{index:08}`, so that entries sharing a prefix get distinct usernames for distinct indices.
+    ///
+    /// Panics if the username is longer than 31 bytes, as it then wouldn't fit in a field element.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn with_username_prefix(
+        prefix: &str,
+        index: usize,
+        balances: [BigUint; N_CURRENCIES],
+    ) -> Self {
+        let username = format!("{prefix}_{index:08}");
+        assert!(
+            username.len() <= MAX_USERNAME_BYTES,
+            "Username {} is longer than {} bytes",
+            username,
+            MAX_USERNAME_BYTES
+        );
+
+        Entry::new(username, balances).unwrap()
+    }
+}
+
+/// The maximum length of a username that fits in a field element without being reduced
+#[cfg(any(test, feature = "test_utils"))]
+const MAX_USERNAME_BYTES: usize = 31;
+
+/// Returns the 32 least significant bytes of `value` in little-endian, padded with zeros
+fn to_le_bytes_32(value: &BigUint) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    let value_bytes = value.to_bytes_le();
+    let len = value_bytes.len().min(32);
+    bytes[..len].copy_from_slice(&value_bytes[..len]);
+    bytes
+}
+
+/// Generates `count` entries with usernames `{prefix}_00000000`, `{prefix}_00000001`, ... and random balances in `[0, max_balance]`.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn generate_test_entries<const N_CURRENCIES: usize>(
+    prefix: &str,
+    count: usize,
+    max_balance: u64,
+) -> Vec<Entry<N_CURRENCIES>> {
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+
+    (0..count)
+        .map(|index| {
+            let balances = std::array::from_fn(|_| BigUint::from(rng.gen_range(0..=max_balance)));
+            Entry::with_username_prefix(prefix, index, balances)
+        })
+        .collect()
+}
+
+/// Two entries are equal if they have the same username: equality ignores the balances, as only the username matters for the uniqueness of an entry in a tree.
+///
+/// `assert_eq!` on two entries thus doesn't check their balances, compare `balances()` or `compute_leaf()` for that.
+impl<const N_CURRENCIES: usize> PartialEq for Entry<N_CURRENCIES> {
+    fn eq(&self, other: &Self) -> bool {
+        self.username_as_big_uint == other.username_as_big_uint
+    }
+}
+
+/// Like `PartialEq`, equality ignores the balances.
+impl<const N_CURRENCIES: usize> Eq for Entry<N_CURRENCIES> {}
+
+impl<const N_CURRENCIES: usize> PartialOrd for Entry<N_CURRENCIES> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N_CURRENCIES: usize> Ord for Entry<N_CURRENCIES> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.username_as_big_uint.cmp(&other.username_as_big_uint)
+    }
+}
+
+/// Hashes the username only, to agree with `Eq`. Use `to_leaf_bytes` as the key to hash the balances as well.
+impl<const N_CURRENCIES: usize> Hash for Entry<N_CURRENCIES> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.username_as_big_uint.hash(state);
+    }
 }
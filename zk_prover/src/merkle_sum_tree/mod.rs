@@ -1,7 +1,12 @@
+mod audit_report;
+mod compressed_proof;
 mod entry;
 mod mst;
 mod node;
+mod sparse_mst;
 mod tests;
+mod total_balance_proof;
+mod transcript;
 mod tree;
 pub mod utils;
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
@@ -26,8 +31,74 @@ where
     pub path_indices: Vec<Fp>,
 }
 
-pub use entry::Entry;
-pub use mst::Cryptocurrency;
+impl<const N_CURRENCIES: usize> MerkleProof<N_CURRENCIES>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    /// Extends the proof by one level, to a tree whose root has the root of the original tree as one child and the node with hash preimage `new_sibling_preimage` as the other.
+    /// `is_left_sibling` tells whether the new sibling is the left child of the new root.
+    ///
+    /// This turns a proof of a tree into a proof of the tree of double size containing it, and can be chained for several doublings.
+    /// The original proof must have at least one level, since the sibling of a leaf is described by a leaf preimage instead.
+    /// The new root is built at depth 0. With a depth-dependent middle node hash, the extended proof wouldn't verify, as the nodes of the original proof move one level deeper.
+    pub fn extend_to_larger_tree(
+        &self,
+        new_sibling_preimage: [Fp; N_CURRENCIES + 2],
+        is_left_sibling: bool,
+    ) -> MerkleProof<N_CURRENCIES> {
+        let new_sibling = Node::<N_CURRENCIES>::middle_node_from_preimage(&new_sibling_preimage);
+
+        let (root, path_index) = if is_left_sibling {
+            (
+                Node::middle_at_depth(&new_sibling, &self.root, 0),
+                Fp::from(1),
+            )
+        } else {
+            (
+                Node::middle_at_depth(&self.root, &new_sibling, 0),
+                Fp::zero(),
+            )
+        };
+
+        let mut extended_proof = self.clone();
+        extended_proof.root = root;
+        extended_proof
+            .sibling_middle_node_hash_preimages
+            .push(new_sibling_preimage);
+        extended_proof.path_indices.push(path_index);
+        extended_proof
+    }
+}
+
+/// A struct representing a proof that a username is not in a Merkle Sum Tree sorted by username.
+///
+/// Fields:
+/// * `username`: The username proven not to be in the tree
+/// * `lower`: The Merkle Proof of the last entry whose username precedes `username`, if any
+/// * `upper`: The Merkle Proof of the entry right after `lower`, if any. It is either the first entry whose username follows `username` or the first zero entry padding the tree
+///
+/// The proof relies on the entries being sorted by username, which is not enforced by the Merkle Sum Tree itself.
+#[derive(Clone, Debug)]
+pub struct NullProof<const N_CURRENCIES: usize>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    pub username: String,
+    pub lower: Option<MerkleProof<N_CURRENCIES>>,
+    pub upper: Option<MerkleProof<N_CURRENCIES>>,
+}
+
+pub use audit_report::MerkleAuditReport;
+pub use compressed_proof::CompressedMerkleProof;
+#[cfg(any(test, feature = "test_utils"))]
+pub use entry::generate_test_entries;
+pub use entry::{Entry, EntryParseError, OnChainFetchError};
 pub use mst::MerkleSumTree;
+pub use mst::{Cryptocurrency, DuplicateError};
 pub use node::Node;
-pub use tree::Tree;
+pub use sparse_mst::SparseMerkleSumTree;
+pub use total_balance_proof::{verify_total_balance_proof, TotalBalanceProof};
+pub use transcript::TreeTranscript;
+pub use tree::{MerkleVerifyError, TraceEntry, Tree};
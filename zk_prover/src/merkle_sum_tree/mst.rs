@@ -1,9 +1,12 @@
 use crate::merkle_sum_tree::utils::{
-    build_leaves_from_entries, build_merkle_tree_from_leaves, parse_csv_to_entries,
+    big_uint_to_fp, build_leaves_from_entries, build_merkle_tree_from_leaves, fp_to_big_uint,
+    parse_csv_to_entries,
 };
-use crate::merkle_sum_tree::{Entry, Node, Tree};
+use crate::merkle_sum_tree::{Entry, Node, NullProof, Tree};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
+use std::collections::BTreeMap;
+use std::fmt;
 
 /// Merkle Sum Tree Data Structure.
 ///
@@ -17,7 +20,11 @@ use num_bigint::BigUint;
 ///
 /// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
 /// * `N_BYTES`: Range in which each node balance should lie
-#[derive(Debug, Clone)]
+///
+/// The `Debug` output is the abbreviated `Display` output, unless the `debug_tree` feature is enabled,
+/// in which case it contains every node and entry of the tree.
+#[derive(Clone)]
+#[cfg_attr(feature = "debug_tree", derive(Debug))]
 pub struct MerkleSumTree<const N_CURRENCIES: usize, const N_BYTES: usize> {
     root: Node<N_CURRENCIES>,
     nodes: Vec<Vec<Node<N_CURRENCIES>>>,
@@ -38,8 +45,12 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES>
         &self.depth
     }
 
-    fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>] {
-        &self.nodes
+    fn get_node(&self, level: usize, index: usize) -> Option<&Node<N_CURRENCIES>> {
+        self.nodes.get(level).and_then(|layer| layer.get(index))
+    }
+
+    fn leaves_count(&self) -> usize {
+        self.nodes[0].len()
     }
 
     fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES> {
@@ -49,6 +60,69 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES>
     fn cryptocurrencies(&self) -> &[Cryptocurrency] {
         &self.cryptocurrencies
     }
+
+    fn is_sorted(&self) -> bool {
+        self.is_sorted
+    }
+}
+
+/// The error returned when several entries of a tree share the same username.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DuplicateError {
+    /// The entries at `indices` have the same username, whose field element representation is `username_hash`
+    DuplicateUsername {
+        username_hash: Fp,
+        indices: Vec<usize>,
+    },
+}
+
+impl fmt::Display for DuplicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateError::DuplicateUsername {
+                username_hash,
+                indices,
+            } => write!(
+                f,
+                "Username {:?} appears at indices {:?}",
+                username_hash, indices
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DuplicateError {}
+
+/// Returns the number of distinct usernames of `entries`, each paired with its index in the tree, or the first username shared by several entries.
+///
+/// Usernames are compared as the field elements hashed into the leaves, so two usernames that map to the same field element are duplicates.
+/// The zero entries padding the tree are skipped.
+pub(super) fn check_unique_usernames<'a, const N_CURRENCIES: usize>(
+    entries: impl Iterator<Item = (usize, &'a Entry<N_CURRENCIES>)>,
+) -> Result<usize, DuplicateError> {
+    let mut indices_by_username: BTreeMap<[u8; 32], Vec<usize>> = BTreeMap::new();
+    for (index, entry) in entries {
+        if entry.is_zero_entry() {
+            continue;
+        }
+
+        indices_by_username
+            .entry(big_uint_to_fp(entry.username_as_big_uint()).to_bytes())
+            .or_default()
+            .push(index);
+    }
+
+    if let Some((username, indices)) = indices_by_username
+        .iter()
+        .find(|(_, indices)| indices.len() > 1)
+    {
+        return Err(DuplicateError::DuplicateUsername {
+            username_hash: Option::from(Fp::from_bytes(username)).unwrap(),
+            indices: indices.clone(),
+        });
+    }
+
+    Ok(indices_by_username.len())
 }
 
 #[derive(Debug, Clone)]
@@ -58,6 +132,10 @@ pub struct Cryptocurrency {
 }
 
 impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Returns the nodes of the tree, level by level starting from the leaves
+    pub fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>] {
+        &self.nodes
+    }
     /// Returns the leaves of the tree
     pub fn leaves(&self) -> &[Node<N_CURRENCIES>] {
         &self.nodes[0]
@@ -81,7 +159,7 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         Self::from_entries(entries, cryptocurrencies, false)
     }
 
-    /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The MST leaves are sorted by the username byte values. The CSV file must be formatted as follows:
+    /// Builds a Merkle Sum Tree from a CSV file stored at `path`. The MST leaves are sorted by the username byte values, in string order, which is not the order of `Entry` (see `Entry`). The CSV file must be formatted as follows:
     ///
     /// `username,balance_<cryptocurrency>_<chain>,balance_<cryptocurrency>_<chain>,...`
     ///
@@ -99,7 +177,9 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         Self::from_entries(entries, cryptocurrencies, true)
     }
 
-    /// Builds a Merkle Sum Tree from a vector of entries
+    /// Builds a Merkle Sum Tree from a vector of entries.
+    ///
+    /// Returns a `DuplicateError` if several entries share the same username.
     pub fn from_entries(
         mut entries: Vec<Entry<N_CURRENCIES>>,
         cryptocurrencies: Vec<Cryptocurrency>,
@@ -109,6 +189,8 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
+        check_unique_usernames(entries.iter().enumerate())?;
+
         let depth = (entries.len() as f64).log2().ceil() as usize;
 
         let mut nodes = vec![];
@@ -125,14 +207,19 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
 
         let root = build_merkle_tree_from_leaves(&leaves, depth, &mut nodes)?;
 
-        Ok(MerkleSumTree {
+        let tree = MerkleSumTree {
             root,
             nodes,
             depth,
             entries,
             cryptocurrencies,
             is_sorted,
-        })
+        };
+
+        #[cfg(debug_assertions)]
+        tree.assert_invariants();
+
+        Ok(tree)
     }
 
     /// Builds a Merkle Sum Tree from a root node, a vector of nodes, a depth, a vector of entries, a vector of cryptocurrencies and a boolean indicating whether the leaves are sorted by the username byte values.
@@ -190,20 +277,87 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
             let left_child = &self.nodes[depth - 1][2 * parent_index];
             let right_child = &self.nodes[depth - 1][2 * parent_index + 1];
 
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance = left_child.balances[i] + right_child.balances[i];
-            }
-            hash_preimage[N_CURRENCIES] = left_child.hash;
-            hash_preimage[N_CURRENCIES + 1] = right_child.hash;
-
-            self.nodes[depth][parent_index] = Node::middle_node_from_preimage(&hash_preimage);
+            self.nodes[depth][parent_index] =
+                Node::middle_at_depth(left_child, right_child, (self.depth - depth) as u32);
             current_index = parent_index;
         }
 
-        let root = self.nodes[self.depth][0].clone();
+        self.root = self.nodes[self.depth][0];
+
+        #[cfg(debug_assertions)]
+        self.assert_invariants();
+
+        Ok(self.root)
+    }
+
+    /// Panics if one of the invariants of the tree is violated:
+    /// * the number of leaves is `2^depth` and each level has half the nodes of the level below
+    /// * each leaf is the leaf computed from the corresponding entry
+    /// * each middle node is the middle node of its children, so that its balances are the sums of the balances of the children
+    /// * the root is the single node of the top level
+    ///
+    /// Only available in debug builds, as it recomputes every node of the tree.
+    /// It runs at the end of `from_entries`, which also builds the trees of `from_csv` and `from_csv_sorted`, and of `update_leaf`.
+    /// `from_params` doesn't run it, so that a tree can be rebuilt from parameters that break the invariants.
+    #[cfg(debug_assertions)]
+    pub fn assert_invariants(&self)
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        assert_eq!(self.nodes.len(), self.depth + 1, "Invalid number of levels");
+        assert_eq!(
+            self.nodes[0].len(),
+            1 << self.depth,
+            "The number of leaves is not 2^depth"
+        );
+        assert_eq!(
+            self.entries.len(),
+            self.nodes[0].len(),
+            "The number of entries doesn't match the number of leaves"
+        );
+
+        for (index, (entry, leaf)) in self.entries.iter().zip(self.nodes[0].iter()).enumerate() {
+            assert_eq!(
+                *leaf,
+                entry.compute_leaf(),
+                "The leaf {} doesn't match its entry",
+                index
+            );
+        }
+
+        for level in 1..=self.depth {
+            let children = &self.nodes[level - 1];
+            assert_eq!(
+                self.nodes[level].len(),
+                children.len() / 2,
+                "Invalid number of nodes at level {}",
+                level
+            );
 
-        Ok(root)
+            for (index, node) in self.nodes[level].iter().enumerate() {
+                let expected = Node::middle_at_depth(
+                    &children[2 * index],
+                    &children[2 * index + 1],
+                    (self.depth - level) as u32,
+                );
+                assert_eq!(
+                    node.balances, expected.balances,
+                    "The balances of the node {} at level {} are not the sums of the balances of its children",
+                    index, level
+                );
+                assert_eq!(
+                    node.hash, expected.hash,
+                    "The hash of the node {} at level {} doesn't match its children",
+                    index, level
+                );
+            }
+        }
+
+        assert_eq!(
+            self.root, self.nodes[self.depth][0],
+            "The root doesn't match the top node"
+        );
     }
 
     /// Returns the index of the leaf with the matching username
@@ -224,4 +378,223 @@ impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES
                 .map_err(|_| Box::from("Username not found"))
         }
     }
+
+    /// Generates a NullProof showing that `username` is not in the tree.
+    ///
+    /// The tree must be sorted by username in string order, as built by `from_csv_sorted`. Usernames are compared as strings, not with the order of `Entry`.
+    pub fn generate_null_proof(
+        &self,
+        username: &str,
+    ) -> Result<NullProof<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if !self.is_sorted {
+            return Err(Box::from("The tree is not sorted by username"));
+        }
+
+        // The zero entries padding the tree are appended after the sorted entries
+        let users_count = self
+            .entries
+            .iter()
+            .position(|entry| entry.is_zero_entry())
+            .unwrap_or(self.entries.len());
+
+        let index = match self.entries[..users_count]
+            .binary_search_by_key(&username, |entry| entry.username())
+        {
+            Ok(_) => return Err(Box::from("Username is in the tree")),
+            Err(index) => index,
+        };
+
+        let lower = if index == 0 {
+            None
+        } else {
+            Some(self.generate_proof(index - 1)?)
+        };
+
+        let upper = if index == self.entries.len() {
+            None
+        } else {
+            Some(self.generate_proof(index)?)
+        };
+
+        Ok(NullProof {
+            username: username.to_string(),
+            lower,
+            upper,
+        })
+    }
+
+    /// Returns the number of users of the tree, checking that no two users share the same username.
+    ///
+    /// Usernames are compared as the field elements hashed into the leaves, so two usernames that map to the same field element are duplicates.
+    /// The zero entries padding the tree are not counted as users.
+    ///
+    /// The trees built from entries are checked on construction, so this can only fail for a tree built with `from_params`.
+    pub fn count_unique_usernames(&self) -> Result<usize, DuplicateError> {
+        check_unique_usernames(self.entries.iter().enumerate())
+    }
+
+    /// Audits the leaves with index in `[start, end)`, returning for each leaf whether its Merkle proof, as built by `generate_proof`, passes `verify_proof`.
+    ///
+    /// The proofs of the range are replayed together, from the leaves up to the root. Like a proof, the audit recomputes the path of each leaf from the entries of the leaf and its sibling,
+    /// and hashes each sibling middle node from the stored children of the sibling, so a stored middle node only affects the leaves under the sibling of its parent.
+    /// The leaves whose recomputed nodes are equal at some level share the rest of their path, which is only hashed once:
+    /// in a consistent tree, a range of `n` leaves costs about `4n + 4 * depth` hashes instead of `n * depth` with one Merkle proof per leaf.
+    /// Each corrupted node splits the leaves it affects from the others, up to the cost of one Merkle proof per leaf.
+    pub fn audit_leaf_range(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<bool>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if start >= end || end > self.nodes[0].len() {
+            return Err(Box::from("Invalid leaf range"));
+        }
+
+        // In a single leaf tree, the proof has an empty path and its leaf is checked against the root
+        if self.depth == 0 {
+            return Ok(vec![self.entries[0].compute_leaf() == self.root]);
+        }
+
+        // The distinct nodes recomputed by the proofs at the current level, each with its index in the level, sorted by index.
+        // The proof of the leaf `start + i` recomputes the node `path_nodes[paths[i]]`.
+        let first_pair = start / 2;
+        let mut path_nodes: Vec<(usize, Node<N_CURRENCIES>)> = (first_pair..=(end - 1) / 2)
+            .map(|index| {
+                let parent = Node::middle_at_depth(
+                    &self.entries[2 * index].compute_leaf(),
+                    &self.entries[2 * index + 1].compute_leaf(),
+                    (self.depth - 1) as u32,
+                );
+                (index, parent)
+            })
+            .collect();
+        let mut paths: Vec<usize> = (start..end).map(|index| index / 2 - first_pair).collect();
+
+        for level in 1..self.depth {
+            let mut parents: Vec<(usize, Node<N_CURRENCIES>)> = Vec::new();
+            let mut sibling: Option<(usize, Node<N_CURRENCIES>)> = None;
+
+            let parent_positions: Vec<usize> = path_nodes
+                .iter()
+                .map(|(index, node)| {
+                    let sibling_index = index ^ 1;
+                    let sibling_node = match sibling {
+                        Some((cached_index, cached_node)) if cached_index == sibling_index => {
+                            cached_node
+                        }
+                        _ => {
+                            let sibling_node = Node::middle_at_depth(
+                                &self.nodes[level - 1][2 * sibling_index],
+                                &self.nodes[level - 1][2 * sibling_index + 1],
+                                (self.depth - level) as u32,
+                            );
+                            sibling = Some((sibling_index, sibling_node));
+                            sibling_node
+                        }
+                    };
+
+                    let parent_depth = (self.depth - level - 1) as u32;
+                    let parent = if index % 2 == 0 {
+                        Node::middle_at_depth(node, &sibling_node, parent_depth)
+                    } else {
+                        Node::middle_at_depth(&sibling_node, node, parent_depth)
+                    };
+
+                    // The nodes recomputed for the same parent are contiguous, as `path_nodes` is sorted by index
+                    let parent_index = index / 2;
+                    match parents
+                        .iter()
+                        .rev()
+                        .take_while(|(other_index, _)| *other_index == parent_index)
+                        .position(|(_, other)| *other == parent)
+                    {
+                        Some(offset) => parents.len() - 1 - offset,
+                        None => {
+                            parents.push((parent_index, parent));
+                            parents.len() - 1
+                        }
+                    }
+                })
+                .collect();
+
+            for path in paths.iter_mut() {
+                *path = parent_positions[*path];
+            }
+            path_nodes = parents;
+        }
+
+        // As in `verify_proof`, the recomputed root must match both the hash and the balances of the root
+        Ok(paths
+            .iter()
+            .map(|path| path_nodes[*path].1 == self.root)
+            .collect())
+    }
+
+    /// Prints the abbreviated `Display` output of the tree to stdout
+    pub fn print_summary(&self) {
+        println!("{}", self);
+    }
+}
+
+/// The number of leaf hashes printed at the start and at the end of the `Display` output
+const DISPLAYED_LEAVES: usize = 5;
+
+/// Returns the first 8 bytes of the big-endian hex representation of `hash`
+fn abbreviate_hash(hash: &Fp) -> String {
+    let hex = format!("{:?}", hash);
+    format!("{}...", &hex[..18])
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> fmt::Display
+    for MerkleSumTree<N_CURRENCIES, N_BYTES>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "MerkleSumTree")?;
+        writeln!(f, "  depth: {}", self.depth)?;
+        writeln!(f, "  leaves: {}", self.nodes[0].len())?;
+        writeln!(f, "  root hash: {}", abbreviate_hash(&self.root.hash))?;
+
+        writeln!(f, "  root balances:")?;
+        for (index, balance) in self.root.balances.iter().enumerate() {
+            match self.cryptocurrencies.get(index) {
+                Some(cryptocurrency) => writeln!(
+                    f,
+                    "    {}_{}: {}",
+                    cryptocurrency.name,
+                    cryptocurrency.chain,
+                    fp_to_big_uint(*balance)
+                )?,
+                None => writeln!(f, "    {}: {}", index, fp_to_big_uint(*balance))?,
+            }
+        }
+
+        writeln!(f, "  leaf hashes:")?;
+        let leaves = &self.nodes[0];
+        for (index, leaf) in leaves.iter().enumerate() {
+            if index == DISPLAYED_LEAVES && leaves.len() > 2 * DISPLAYED_LEAVES {
+                writeln!(f, "    ...")?;
+            }
+            if index < DISPLAYED_LEAVES || index + DISPLAYED_LEAVES >= leaves.len() {
+                writeln!(f, "    {}: {}", index, abbreviate_hash(&leaf.hash))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "debug_tree"))]
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> fmt::Debug
+    for MerkleSumTree<N_CURRENCIES, N_BYTES>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
 }
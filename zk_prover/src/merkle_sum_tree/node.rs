@@ -4,7 +4,7 @@ use halo2_gadgets::poseidon::primitives::{self as poseidon, ConstantLength};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
 use num_bigint::BigUint;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Node<const N_CURRENCIES: usize> {
     pub hash: Fp,
     pub balances: [Fp; N_CURRENCIES],
@@ -31,7 +31,7 @@ impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
     /// The balances are equal to `LeftChild.balance[0] + RightChild.balance[0], LeftChild.balance[1] + RightChild.balance[1], ..., LeftChild.balance[N_CURRENCIES - 1] + RightChild.balance[N_CURRENCIES - 1]`
     pub fn middle(child_l: &Node<N_CURRENCIES>, child_r: &Node<N_CURRENCIES>) -> Node<N_CURRENCIES>
     where
-        [(); N_CURRENCIES + 2]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
     {
         let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
         for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
@@ -43,6 +43,22 @@ impl<const N_CURRENCIES: usize> Node<N_CURRENCIES> {
         Node::middle_node_from_preimage(&hash_preimage)
     }
 
+    /// Builds a middle node at distance `depth` from the root, the root being at depth 0 and the parents of the leaves at depth `tree_depth - 1`.
+    ///
+    /// This is the hook for hash variants that depend on the depth. The hash is currently the same at every depth, equal to `Node::middle`.
+    /// Every middle node of the tree and the proofs is built through this function, but the circuit hashes the middle nodes the same way at every depth,
+    /// so it must be updated along with any depth-dependent variant.
+    pub fn middle_at_depth(
+        child_l: &Node<N_CURRENCIES>,
+        child_r: &Node<N_CURRENCIES>,
+        _depth: u32,
+    ) -> Node<N_CURRENCIES>
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        Node::middle(child_l, child_r)
+    }
+
     /// Returns an empty node where the hash is 0 and the balances are all 0
     pub fn init_empty() -> Node<N_CURRENCIES>
     where
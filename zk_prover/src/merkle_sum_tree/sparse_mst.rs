@@ -0,0 +1,148 @@
+use crate::merkle_sum_tree::mst::check_unique_usernames;
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, Node, Tree};
+use std::collections::BTreeMap;
+
+/// A Merkle Sum Tree of which most entries are zero entries, e.g. when only a few users of an exchange hold a balance.
+///
+/// Subtrees made of zero entries only hash identically at each level, so a single zero node is stored per level.
+/// Apart from those, only the non-zero entries and the nodes on their paths to the root are stored:
+/// the memory grows as `non_zero_entries.len() * depth` instead of `2^depth` for the dense `MerkleSumTree`.
+/// The root, nodes and proofs are identical to the ones of the `MerkleSumTree` built by `from_entries` over all the entries,
+/// and as it implements `Tree`, it can be used wherever a `Tree` trait object is expected.
+///
+/// # Type Parameters
+///
+/// * `N_CURRENCIES`: The number of cryptocurrencies for each user account
+/// * `N_BYTES`: Range in which each node balance should lie
+#[derive(Debug, Clone)]
+pub struct SparseMerkleSumTree<const N_CURRENCIES: usize, const N_BYTES: usize> {
+    root: Node<N_CURRENCIES>,
+    /// The nodes of each level on the path from a non-zero entry to the root, by index
+    nodes: Vec<BTreeMap<usize, Node<N_CURRENCIES>>>,
+    /// `zero_nodes[level]` is the root of a subtree of height `level` made of zero entries only
+    zero_nodes: Vec<Node<N_CURRENCIES>>,
+    depth: usize,
+    entries: BTreeMap<usize, Entry<N_CURRENCIES>>,
+    zero_entry: Entry<N_CURRENCIES>,
+    cryptocurrencies: Vec<Cryptocurrency>,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> Tree<N_CURRENCIES>
+    for SparseMerkleSumTree<N_CURRENCIES, N_BYTES>
+{
+    fn root(&self) -> &Node<N_CURRENCIES> {
+        &self.root
+    }
+
+    fn depth(&self) -> &usize {
+        &self.depth
+    }
+
+    fn get_node(&self, level: usize, index: usize) -> Option<&Node<N_CURRENCIES>> {
+        if level > self.depth || index >= 1 << (self.depth - level) {
+            return None;
+        }
+
+        self.nodes[level]
+            .get(&index)
+            .or(Some(&self.zero_nodes[level]))
+    }
+
+    fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES> {
+        self.entries.get(&index).unwrap_or(&self.zero_entry)
+    }
+
+    fn cryptocurrencies(&self) -> &[Cryptocurrency] {
+        &self.cryptocurrencies
+    }
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> SparseMerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Builds a Merkle Sum Tree of `total_size` entries from the non-zero entries only, each paired with its index in the tree.
+    /// All the other entries are zero entries.
+    ///
+    /// Only the nodes on the path from a non-zero entry to the root are hashed.
+    ///
+    /// Returns a `DuplicateError` if several entries share the same username.
+    pub fn new(
+        non_zero_entries: &[(usize, Entry<N_CURRENCIES>)],
+        total_size: usize,
+        cryptocurrencies: Vec<Cryptocurrency>,
+    ) -> Result<SparseMerkleSumTree<N_CURRENCIES, N_BYTES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let depth = (total_size as f64).log2().ceil() as usize;
+
+        if non_zero_entries
+            .iter()
+            .any(|(index, _)| *index >= total_size)
+        {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        check_unique_usernames(
+            non_zero_entries
+                .iter()
+                .map(|(index, entry)| (*index, entry)),
+        )?;
+
+        let entries: BTreeMap<usize, Entry<N_CURRENCIES>> =
+            non_zero_entries.iter().cloned().collect();
+
+        let zero_entry = Entry::<N_CURRENCIES>::zero_entry();
+        let mut zero_nodes = vec![zero_entry.compute_leaf()];
+        for level in 1..=depth {
+            zero_nodes.push(Node::middle_at_depth(
+                &zero_nodes[level - 1],
+                &zero_nodes[level - 1],
+                (depth - level) as u32,
+            ));
+        }
+
+        let mut nodes = vec![BTreeMap::new(); depth + 1];
+        nodes[0] = entries
+            .iter()
+            .map(|(index, entry)| (*index, entry.compute_leaf()))
+            .collect();
+
+        // Compute the nodes on the paths from the non-zero entries up to the root
+        for level in 1..=depth {
+            let mut parent_indices: Vec<usize> =
+                nodes[level - 1].keys().map(|index| index / 2).collect();
+            parent_indices.dedup();
+
+            for index in parent_indices {
+                let child = |child_index: usize| {
+                    nodes[level - 1]
+                        .get(&child_index)
+                        .unwrap_or(&zero_nodes[level - 1])
+                };
+                let node = Node::middle_at_depth(
+                    child(2 * index),
+                    child(2 * index + 1),
+                    (depth - level) as u32,
+                );
+                nodes[level].insert(index, node);
+            }
+        }
+
+        let root = *nodes[depth].get(&0).unwrap_or(&zero_nodes[depth]);
+
+        Ok(SparseMerkleSumTree {
+            root,
+            nodes,
+            zero_nodes,
+            depth,
+            entries,
+            zero_entry,
+            cryptocurrencies,
+        })
+    }
+
+    /// Returns the number of nodes stored by the tree, the zero node of each level included
+    pub fn stored_nodes_count(&self) -> usize {
+        self.nodes.iter().map(BTreeMap::len).sum::<usize>() + self.zero_nodes.len()
+    }
+}
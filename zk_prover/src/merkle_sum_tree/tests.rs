@@ -1,10 +1,18 @@
 #[cfg(test)]
 mod test {
 
-    use crate::merkle_sum_tree::utils::big_uint_to_fp;
-    use crate::merkle_sum_tree::{Entry, MerkleSumTree, Node, Tree};
+    use crate::merkle_sum_tree::utils::{
+        big_uint_to_fp, fp_from_be_bytes, fp_from_le_bytes, ConversionError,
+    };
+    use crate::merkle_sum_tree::{
+        generate_test_entries, verify_total_balance_proof, DuplicateError, Entry, EntryParseError,
+        MerkleAuditReport, MerkleSumTree, MerkleVerifyError, Node, NullProof, SparseMerkleSumTree,
+        Tree,
+    };
+    use halo2_proofs::halo2curves::bn256::Fr as Fp;
     use num_bigint::{BigUint, ToBigUint};
     use rand::Rng as _;
+    use std::collections::HashMap;
 
     const N_CURRENCIES: usize = 2;
     const N_BYTES: usize = 8;
@@ -29,7 +37,7 @@ mod test {
         let proof = merkle_tree.generate_proof(0).unwrap();
 
         // verify proof
-        assert!(merkle_tree.verify_proof(&proof));
+        assert!(merkle_tree.verify_proof(&proof).is_ok());
 
         // Should generate different root hashes when changing the entry order
         let merkle_tree_2 =
@@ -43,7 +51,7 @@ mod test {
         // should create valid proof for each entry in the tree and verify it
         for i in 0..=15 {
             let proof = merkle_tree.generate_proof(i).unwrap();
-            assert!(merkle_tree.verify_proof(&proof));
+            assert!(merkle_tree.verify_proof(&proof).is_ok());
         }
 
         // shouldn't create a proof for an entry that doesn't exist in the tree
@@ -58,12 +66,33 @@ mod test {
         let invalid_entry = invalid_entry;
         let mut proof_invalid_1 = proof.clone();
         proof_invalid_1.entry = invalid_entry;
-        assert!(!merkle_tree.verify_proof(&proof_invalid_1));
+        assert!(matches!(
+            merkle_tree.verify_proof(&proof_invalid_1),
+            Err(MerkleVerifyError::HashMismatch { level: 0, .. })
+        ));
+        assert!(!merkle_tree.verify_proof_bool(&proof_invalid_1));
 
         // shouldn't verify a proof with a wrong root hash
         let mut proof_invalid_2 = proof.clone();
         proof_invalid_2.root.hash = 0.into();
-        assert!(!merkle_tree.verify_proof(&proof_invalid_2));
+        assert_eq!(
+            merkle_tree.verify_proof(&proof_invalid_2),
+            Err(MerkleVerifyError::RootHashMismatch {
+                proof_root: 0.into(),
+                computed_root: root.hash,
+            })
+        );
+
+        // shouldn't verify a proof with a wrong root balance
+        let mut proof_invalid_3 = proof.clone();
+        proof_invalid_3.root.balances[1] = 0.into();
+        assert_eq!(
+            merkle_tree.verify_proof(&proof_invalid_3),
+            Err(MerkleVerifyError::BalanceMismatch {
+                expected: 0.into(),
+                got: root.balances[1],
+            })
+        );
     }
 
     #[test]
@@ -149,6 +178,92 @@ mod test {
         assert_eq!(fp_3, 18446744073709551613.into());
     }
 
+    #[test]
+    fn test_fp_from_bytes_conversion() {
+        // 0x0100 = 256
+        assert_eq!(fp_from_be_bytes(&[0x01, 0x00]).unwrap(), 256.into());
+        assert_eq!(fp_from_be_bytes(&[]).unwrap(), 0.into());
+
+        // 2^64 requires 9 bytes and should match the BigUint conversion
+        let be_bytes = [0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+        let big_uint = BigUint::from_bytes_be(&be_bytes);
+        assert_eq!(
+            fp_from_be_bytes(&be_bytes).unwrap(),
+            big_uint_to_fp(&big_uint)
+        );
+
+        // 31 bytes is the maximum accepted length
+        assert!(fp_from_be_bytes(&[0xff; 31]).is_ok());
+        assert_eq!(
+            fp_from_be_bytes(&[0xff; 32]),
+            Err(ConversionError::InputTooLong(32))
+        );
+
+        // little-endian conversion should round trip with `to_bytes`
+        let fp = big_uint_to_fp(&big_uint);
+        assert_eq!(fp_from_le_bytes(&fp.to_bytes()).unwrap(), fp);
+
+        // 2^256 - 1 is larger than the field modulus
+        assert_eq!(
+            fp_from_le_bytes(&[0xff; 32]),
+            Err(ConversionError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn test_entry_to_leaf_bytes() {
+        let entry = Entry::<N_CURRENCIES>::new(
+            "a".to_string(),
+            [256.to_biguint().unwrap(), 3.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        // "a" is encoded as 97
+        let bytes = entry.to_leaf_bytes();
+        assert_eq!(bytes[0], 97);
+        assert!(bytes[1..32].iter().all(|byte| *byte == 0));
+        // With multiple currencies, the last 32 bytes are the hash of the 32-byte little-endian balances
+        let mut balances_bytes = [0u8; 64];
+        balances_bytes[1] = 1;
+        balances_bytes[32] = 3;
+        assert_eq!(bytes[32..], ethers::utils::keccak256(balances_bytes));
+
+        // With a single currency, the last 32 bytes are the balance
+        let single_currency_entry =
+            Entry::<1>::new("a".to_string(), [256.to_biguint().unwrap()]).unwrap();
+        let bytes = single_currency_entry.to_leaf_bytes();
+        assert_eq!(bytes[32], 0);
+        assert_eq!(bytes[33], 1);
+        assert!(bytes[34..].iter().all(|byte| *byte == 0));
+
+        // Entries can be used as map keys, where only the username counts
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let indices: HashMap<Entry<N_CURRENCIES>, usize> = merkle_tree
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.clone(), index))
+            .collect();
+
+        assert_eq!(indices.len(), 16);
+        assert_eq!(indices[&merkle_tree.entries()[7]], 7);
+
+        // The leaf bytes tell apart two entries of the same user with different balances
+        let same_user = Entry::<N_CURRENCIES>::new(
+            "a".to_string(),
+            [256.to_biguint().unwrap(), 4.to_biguint().unwrap()],
+        )
+        .unwrap();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(&entry, &mut hasher);
+        let entry_hash = std::hash::Hasher::finish(&hasher);
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(&same_user, &mut hasher);
+        assert_eq!(entry_hash, std::hash::Hasher::finish(&hasher));
+        assert_ne!(entry.to_leaf_bytes(), same_user.to_leaf_bytes());
+    }
+
     #[test]
     fn get_middle_node_hash_preimage() {
         let merkle_tree =
@@ -165,7 +280,7 @@ mod test {
         let index = rng.gen_range(0..merkle_tree.nodes()[level].len());
 
         // Fetch middle node with index from level
-        let middle_node = merkle_tree.nodes()[level][index].clone();
+        let middle_node = merkle_tree.nodes()[level][index];
 
         // Fetch the hash preimage of the middle node
         let hash_preimage = merkle_tree
@@ -188,7 +303,7 @@ mod test {
         let index = rng.gen_range(0..16);
 
         // Fetch leaf with index
-        let leaf = merkle_tree.leaves()[index].clone();
+        let leaf = merkle_tree.leaves()[index];
 
         // Fetch the hash preimage of the leaf
         let hash_preimage = merkle_tree.get_leaf_node_hash_preimage(index).unwrap();
@@ -211,7 +326,7 @@ mod test {
         // The last 3 entries of the merkle tree should be zero entries
         for i in 13..16 {
             let entry = merkle_tree.entries()[i].clone();
-            assert_eq!(entry, Entry::<N_CURRENCIES>::zero_entry());
+            assert!(entry.is_zero_entry());
         }
 
         // expect root hash to be different than 0
@@ -224,7 +339,7 @@ mod test {
         // should create valid proof for each entry in the tree and verify it
         for i in 0..=15 {
             let proof = merkle_tree.generate_proof(i).unwrap();
-            assert!(merkle_tree.verify_proof(&proof));
+            assert!(merkle_tree.verify_proof(&proof).is_ok());
         }
 
         // shouldn't create a proof for an entry that doesn't exist in the tree
@@ -243,7 +358,7 @@ mod test {
         // The last 15 entries of the merkle tree should be zero entries
         for i in 17..32 {
             let entry = merkle_tree.entries()[i].clone();
-            assert_eq!(entry, Entry::<N_CURRENCIES>::zero_entry());
+            assert!(entry.is_zero_entry());
         }
 
         // expect root hash to be different than 0
@@ -256,10 +371,1171 @@ mod test {
         // should create valid proof for each entry in the tree and verify it
         for i in 0..=31 {
             let proof = merkle_tree.generate_proof(i).unwrap();
-            assert!(merkle_tree.verify_proof(&proof));
+            assert!(merkle_tree.verify_proof(&proof).is_ok());
         }
 
         // shouldn't create a proof for an entry that doesn't exist in the tree
         assert!(merkle_tree.generate_proof(32).is_err());
     }
+
+    #[test]
+    fn test_sparse_mst() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // Scatter 3 entries of the csv among the 32 leaves of the tree
+        let non_zero_entries: Vec<(usize, Entry<N_CURRENCIES>)> = [(1, 0), (7, 1), (30, 2)]
+            .iter()
+            .map(|(index, csv_index)| (*index, merkle_tree.entries()[*csv_index].clone()))
+            .collect();
+
+        let mut entries = vec![Entry::<N_CURRENCIES>::zero_entry(); 32];
+        for (index, entry) in &non_zero_entries {
+            entries[*index] = entry.clone();
+        }
+
+        let dense_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        let sparse_merkle_tree = SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+            &non_zero_entries,
+            32,
+            merkle_tree.cryptocurrencies().to_vec(),
+        )
+        .unwrap();
+
+        // The sparse tree should be identical to the dense one
+        assert_eq!(sparse_merkle_tree.root(), dense_merkle_tree.root());
+        assert_eq!(sparse_merkle_tree.leaves_count(), 32);
+        for level in 0..=5 {
+            for index in 0..(32 >> level) + 1 {
+                assert_eq!(
+                    sparse_merkle_tree.get_node(level, index),
+                    dense_merkle_tree.get_node(level, index)
+                );
+            }
+        }
+        assert_eq!(sparse_merkle_tree.get_node(6, 0), None);
+
+        for i in 0..32 {
+            assert_eq!(
+                sparse_merkle_tree.get_entry(i),
+                dense_merkle_tree.get_entry(i)
+            );
+
+            let proof = sparse_merkle_tree.generate_proof(i).unwrap();
+            let dense_proof = dense_merkle_tree.generate_proof(i).unwrap();
+            assert_eq!(
+                proof.sibling_leaf_node_hash_preimage,
+                dense_proof.sibling_leaf_node_hash_preimage
+            );
+            assert_eq!(
+                proof.sibling_middle_node_hash_preimages,
+                dense_proof.sibling_middle_node_hash_preimages
+            );
+            assert!(sparse_merkle_tree.verify_proof(&proof).is_ok());
+            assert!(dense_merkle_tree.verify_proof(&proof).is_ok());
+        }
+        assert!(sparse_merkle_tree.generate_proof(32).is_err());
+
+        // Only the 14 nodes on the paths of the 3 non-zero entries are stored, along with the 6 zero nodes, out of the 63 nodes of the tree
+        assert_eq!(sparse_merkle_tree.stored_nodes_count(), 20);
+
+        // shouldn't build a tree with an entry outside of the tree
+        assert!(SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+            &non_zero_entries,
+            16,
+            merkle_tree.cryptocurrencies().to_vec(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_sparse_mst_memory() {
+        const DEPTH: usize = 20;
+        const N_NON_ZERO_ENTRIES: usize = 1000;
+
+        // Spread the non-zero entries evenly among the 2^20 leaves of the tree
+        let total_size = 1 << DEPTH;
+        let step = total_size / N_NON_ZERO_ENTRIES;
+        let non_zero_entries: Vec<(usize, Entry<N_CURRENCIES>)> = (0..N_NON_ZERO_ENTRIES)
+            .map(|i| {
+                let balance = BigUint::from(i as u64 + 1);
+                let entry = Entry::new(format!("user{}", i), [balance.clone(), balance]).unwrap();
+                (i * step, entry)
+            })
+            .collect();
+
+        let sparse_merkle_tree = SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+            &non_zero_entries,
+            total_size,
+            vec![],
+        )
+        .unwrap();
+
+        // At most one node per level is stored for each non-zero entry, plus the zero node of each level,
+        // while the dense tree stores every one of its 2^21 - 1 nodes
+        let dense_nodes_count = (1 << (DEPTH + 1)) - 1;
+        let max_stored_nodes_count = (N_NON_ZERO_ENTRIES + 1) * (DEPTH + 1);
+        assert!(sparse_merkle_tree.stored_nodes_count() <= max_stored_nodes_count);
+        assert!(max_stored_nodes_count * 50 < dense_nodes_count);
+
+        let proof = sparse_merkle_tree.generate_proof(step).unwrap();
+        assert!(sparse_merkle_tree.verify_proof(&proof).is_ok());
+    }
+
+    #[test]
+    fn test_get_proof_iterative() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // The iterative proof should be identical to the one of `generate_proof`
+        for i in 0..16 {
+            let proof = merkle_tree.generate_proof(i).unwrap();
+            let iterative_proof = merkle_tree.get_proof_iterative(i).unwrap();
+
+            assert_eq!(
+                iterative_proof.entry.compute_leaf(),
+                proof.entry.compute_leaf()
+            );
+            assert_eq!(iterative_proof.root, proof.root);
+            assert_eq!(
+                iterative_proof.sibling_leaf_node_hash_preimage,
+                proof.sibling_leaf_node_hash_preimage
+            );
+            assert_eq!(
+                iterative_proof.sibling_middle_node_hash_preimages,
+                proof.sibling_middle_node_hash_preimages
+            );
+            assert_eq!(iterative_proof.path_indices, proof.path_indices);
+        }
+
+        assert!(merkle_tree.get_proof_iterative(16).is_err());
+    }
+
+    #[test]
+    fn test_get_proof_iterative_depth_25() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // A tree of 2^25 leaves only fits in memory as a sparse tree
+        let total_size = 1 << 25;
+        let non_zero_entries: Vec<(usize, Entry<N_CURRENCIES>)> = [0, 12345, total_size - 1]
+            .iter()
+            .enumerate()
+            .map(|(csv_index, index)| (*index, merkle_tree.entries()[csv_index].clone()))
+            .collect();
+
+        let sparse_merkle_tree = SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+            &non_zero_entries,
+            total_size,
+            merkle_tree.cryptocurrencies().to_vec(),
+        )
+        .unwrap();
+        assert_eq!(*sparse_merkle_tree.depth(), 25);
+
+        for (index, entry) in &non_zero_entries {
+            let proof = sparse_merkle_tree.get_proof_iterative(*index).unwrap();
+            assert_eq!(proof.entry.compute_leaf(), entry.compute_leaf());
+            assert_eq!(proof.path_indices.len(), 25);
+            assert_eq!(proof.sibling_middle_node_hash_preimages.len(), 24);
+            assert!(sparse_merkle_tree.verify_proof(&proof).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_audit_report() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let report = merkle_tree.generate_audit_report(1);
+
+        assert!(report.is_fully_valid());
+        assert_eq!(report.timestamp, 1);
+        assert_eq!(report.root_hash, merkle_tree.root().hash.to_bytes());
+        assert_eq!(report.total_leaves, 16);
+        assert_eq!(report.verified_count, 16);
+        assert_eq!(report.tree_depth, 4);
+        assert_eq!(
+            report.total_balance_per_currency,
+            vec![556862.to_biguint().unwrap(), 556862.to_biguint().unwrap()]
+        );
+
+        // The report should survive a JSON round trip
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed_report: MerkleAuditReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed_report, report);
+
+        // Replace the 5th entry without updating the nodes of the tree
+        let mut entries = merkle_tree.entries().to_vec();
+        entries[5] = Entry::new(
+            "AtwIxZHo".to_string(),
+            [35479.to_biguint().unwrap(), 35479.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        let corrupted_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            merkle_tree.nodes().to_vec(),
+            *merkle_tree.depth(),
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        // The proof of the 4th leaf fails too, as its sibling leaf preimage is computed from the 5th entry
+        let report = corrupted_merkle_tree.generate_audit_report(1);
+        assert!(!report.is_fully_valid());
+        assert_eq!(report.failed_indices, vec![4, 5]);
+        assert_eq!(report.verified_count, 14);
+    }
+
+    #[test]
+    fn test_mst_display() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let summary = format!("{}", merkle_tree);
+
+        assert!(summary.contains("depth: 4"));
+        assert!(summary.contains("leaves: 16"));
+        assert!(summary.contains("ETH_ETH: 556862"));
+        assert!(summary.contains("USDT_ETH: 556862"));
+
+        // Only the first 5 and the last 5 leaves should be listed
+        assert!(summary.contains("    4: 0x"));
+        assert!(!summary.contains("    5: 0x"));
+        assert!(!summary.contains("    10: 0x"));
+        assert!(summary.contains("    11: 0x"));
+        assert!(summary.contains("    ..."));
+    }
+
+    #[cfg(not(feature = "debug_tree"))]
+    #[test]
+    fn test_mst_debug() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        // Without the `debug_tree` feature, the `Debug` output is the summary
+        let debug = format!("{:?}", merkle_tree);
+        assert_eq!(debug, format!("{}", merkle_tree));
+        assert!(debug.contains("  depth: 4"));
+    }
+
+    #[test]
+    fn test_null_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv_sorted("../csv/entry_16.csv").unwrap();
+
+        // "Bob" is between "AtwIxZHo" and "HfMDmNLp"
+        let null_proof = merkle_tree.generate_null_proof("Bob").unwrap();
+        assert_eq!(
+            null_proof.lower.as_ref().unwrap().entry.username(),
+            "AtwIxZHo"
+        );
+        assert_eq!(
+            null_proof.upper.as_ref().unwrap().entry.username(),
+            "HfMDmNLp"
+        );
+        assert!(merkle_tree.verify_null_proof(&null_proof));
+
+        // Usernames before the first and after the last entry
+        let null_proof = merkle_tree.generate_null_proof("Alice").unwrap();
+        assert!(null_proof.lower.is_none());
+        assert!(merkle_tree.verify_null_proof(&null_proof));
+
+        let null_proof = merkle_tree.generate_null_proof("zoe").unwrap();
+        assert!(null_proof.upper.is_none());
+        assert!(merkle_tree.verify_null_proof(&null_proof));
+
+        // A username in the tree can't get a null proof
+        assert!(merkle_tree.generate_null_proof("dxGaEAii").is_err());
+
+        // Claiming that "Bob" is between non adjacent entries should fail
+        let mut null_proof = merkle_tree.generate_null_proof("Bob").unwrap();
+        null_proof.upper = Some(merkle_tree.generate_proof(2).unwrap());
+        assert!(!merkle_tree.verify_null_proof(&null_proof));
+
+        // Reusing the null proof for a username outside the bracket should fail
+        let mut null_proof = merkle_tree.generate_null_proof("Bob").unwrap();
+        null_proof.username = "MBlfbBGI".to_string();
+        assert!(!merkle_tree.verify_null_proof(&null_proof));
+
+        // Null proofs require a sorted tree
+        let unsorted_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        assert!(unsorted_merkle_tree.generate_null_proof("Bob").is_err());
+
+        // In an unsorted tree, adjacent leaves can bracket a username stored elsewhere in the tree,
+        // so a null proof made of valid proofs of such leaves should fail
+        let entries = unsorted_merkle_tree.entries();
+        let (index, username) = (0..15)
+            .find_map(|index| {
+                let (lower, upper) = (entries[index].username(), entries[index + 1].username());
+                entries
+                    .iter()
+                    .map(|entry| entry.username())
+                    .find(|username| lower < *username && *username < upper)
+                    .map(|username| (index, username.to_string()))
+            })
+            .unwrap();
+        let null_proof = NullProof {
+            username,
+            lower: Some(unsorted_merkle_tree.generate_proof(index).unwrap()),
+            upper: Some(unsorted_merkle_tree.generate_proof(index + 1).unwrap()),
+        };
+        assert!(unsorted_merkle_tree
+            .index_of_username(&null_proof.username)
+            .is_ok());
+        assert!(!unsorted_merkle_tree.verify_null_proof(&null_proof));
+
+        // In a padded tree, the first zero entry closes the range of the usernames
+        let padded_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv_sorted("../csv/entry_13.csv").unwrap();
+        let null_proof = padded_merkle_tree.generate_null_proof("zoe").unwrap();
+        assert!(null_proof.upper.as_ref().unwrap().entry.is_zero_entry());
+        assert!(padded_merkle_tree.verify_null_proof(&null_proof));
+
+        // A null proof made of valid proofs of another tree should fail
+        assert!(!merkle_tree.verify_null_proof(&null_proof));
+        let null_proof = padded_merkle_tree.generate_null_proof("Bob").unwrap();
+        assert!(padded_merkle_tree.verify_null_proof(&null_proof));
+        assert!(!merkle_tree.verify_null_proof(&null_proof));
+    }
+
+    #[test]
+    fn test_generate_test_entries() {
+        let entry = Entry::<N_CURRENCIES>::with_username_prefix(
+            "user",
+            42,
+            [1u32.to_biguint().unwrap(), 2u32.to_biguint().unwrap()],
+        );
+        assert_eq!(entry.username(), "user_00000042");
+
+        let entries = generate_test_entries::<N_CURRENCIES>("user", 100, 1000);
+        assert_eq!(entries.len(), 100);
+        assert_eq!(entries[99].username(), "user_00000099");
+
+        let max_balance = 1000u32.to_biguint().unwrap();
+        for entry in entries.iter() {
+            assert!(entry
+                .balances()
+                .iter()
+                .all(|balance| *balance <= max_balance));
+        }
+
+        // The generated entries can be used to build a tree
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(entries, vec![], false).unwrap();
+        assert_eq!(*merkle_tree.depth(), 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_username_prefix_too_long() {
+        Entry::<N_CURRENCIES>::with_username_prefix(
+            "a_very_long_username_prefix",
+            0,
+            [0u32.to_biguint().unwrap(), 0u32.to_biguint().unwrap()],
+        );
+    }
+
+    #[test]
+    fn test_verify_proof_traced() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_tree.generate_proof(6).unwrap();
+        let (is_valid, trace) = merkle_tree.verify_proof_traced(&proof).unwrap();
+
+        assert!(is_valid);
+        assert_eq!(trace.len(), 5);
+        assert_eq!(trace[4].computed_hash, merkle_tree.root().hash);
+        assert_eq!(trace[4].computed_balances, merkle_tree.root().balances);
+        for (level, trace_entry) in trace.iter().enumerate() {
+            assert_eq!(trace_entry.level, level);
+            assert_eq!(trace_entry.expected_hash, Some(trace_entry.computed_hash));
+        }
+
+        // Tamper with the balances of the entry, the trace should diverge from the leaf level
+        let mut proof = merkle_tree.generate_proof(6).unwrap();
+        proof.entry = Entry::new(
+            proof.entry.username().to_string(),
+            [0.to_biguint().unwrap(), 0.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        let (is_valid, trace) = merkle_tree.verify_proof_traced(&proof).unwrap();
+        assert!(!is_valid);
+        assert_eq!(trace.len(), 5);
+        assert_ne!(trace[0].expected_hash, Some(trace[0].computed_hash));
+    }
+
+    #[test]
+    fn test_verify_truncated_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let r = Fp::from(rand::thread_rng().gen::<u64>());
+
+        // A proof missing a sibling middle node is rejected instead of panicking
+        let mut truncated_proof = merkle_tree.generate_proof(6).unwrap();
+        truncated_proof.sibling_middle_node_hash_preimages.pop();
+
+        let expected_error = MerkleVerifyError::PathLengthMismatch {
+            path_length: 4,
+            sibling_middle_nodes: 2,
+        };
+        assert_eq!(
+            merkle_tree.verify_proof(&truncated_proof),
+            Err(expected_error.clone())
+        );
+        assert_eq!(
+            merkle_tree.verify_proof_traced(&truncated_proof),
+            Err(expected_error)
+        );
+        assert!(!merkle_tree.verify_proofs_rng_lc(&[truncated_proof], r));
+
+        // A proof with an empty path is rejected the same way by both verifications
+        let mut empty_proof = merkle_tree.generate_proof(6).unwrap();
+        empty_proof.path_indices.clear();
+        empty_proof.sibling_middle_node_hash_preimages.clear();
+
+        assert_eq!(
+            merkle_tree.verify_proof(&empty_proof),
+            Err(MerkleVerifyError::EmptyPath { depth: 4 })
+        );
+        assert_eq!(
+            merkle_tree.verify_proof_traced(&empty_proof),
+            Err(MerkleVerifyError::EmptyPath { depth: 4 })
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_verify_proof_traced_debug() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut proof = merkle_tree.generate_proof(6).unwrap();
+        assert!(merkle_tree.verify_proof_traced_debug(&proof));
+
+        // Leaf 6 is a left child, claim it is a right child
+        proof.path_indices[0] = Fp::from(1);
+        assert!(!merkle_tree.verify_proof_traced_debug(&proof));
+
+        proof.sibling_middle_node_hash_preimages.pop();
+        assert!(!merkle_tree.verify_proof_traced_debug(&proof));
+    }
+
+    #[test]
+    fn test_compress_merkle_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_tree.generate_proof(6).unwrap();
+        let compressed_proof = proof.compress();
+
+        assert_eq!(compressed_proof.leaf_index, 6);
+        assert_eq!(
+            compressed_proof.root_hash,
+            merkle_tree.root().hash.to_bytes()
+        );
+        assert_eq!(compressed_proof.path_indices, vec![0, 1, 1, 0]);
+        assert_eq!(compressed_proof.sibling_hashes.len(), 4);
+        assert_eq!(compressed_proof.sibling_sums.len(), 4 * N_CURRENCIES);
+        assert_eq!(
+            compressed_proof.sibling_hashes[0],
+            merkle_tree.leaves()[7].hash.to_bytes()
+        );
+
+        let restored_proof = compressed_proof.restore(&merkle_tree).unwrap();
+        // Entries are equal when their usernames are, so the leaves are compared to check the balances too
+        assert_eq!(
+            restored_proof.entry.compute_leaf(),
+            proof.entry.compute_leaf()
+        );
+        assert_eq!(restored_proof.path_indices, proof.path_indices);
+        assert_eq!(
+            restored_proof.sibling_middle_node_hash_preimages,
+            proof.sibling_middle_node_hash_preimages
+        );
+        assert!(merkle_tree.verify_proof(&restored_proof).is_ok());
+
+        // Restoring from a different tree should fail
+        let modified_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16_modified.csv")
+                .unwrap();
+        assert!(compressed_proof.restore(&modified_merkle_tree).is_err());
+    }
+
+    #[test]
+    fn test_total_balance_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_tree.prove_total_balance();
+        assert_eq!(proof.root_hash, merkle_tree.root().hash);
+
+        let claimed_total = [556862.to_biguint().unwrap(), 556862.to_biguint().unwrap()];
+        assert!(verify_total_balance_proof(&proof, &claimed_total));
+
+        let wrong_total = [556862.to_biguint().unwrap(), 556861.to_biguint().unwrap()];
+        assert!(!verify_total_balance_proof(&proof, &wrong_total));
+    }
+
+    #[test]
+    fn test_verify_proof_with_expected_leaf() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let proof = merkle_tree.generate_proof(0).unwrap();
+        let leaf = merkle_tree.leaves()[0];
+        assert!(merkle_tree.verify_proof_with_expected_leaf(&proof, leaf.hash, leaf.balances));
+
+        // The proof of another user is valid but doesn't match the expected leaf
+        let other_proof = merkle_tree.generate_proof(1).unwrap();
+        assert!(merkle_tree.verify_proof(&other_proof).is_ok());
+        assert!(!merkle_tree.verify_proof_with_expected_leaf(
+            &other_proof,
+            leaf.hash,
+            leaf.balances
+        ));
+
+        let wrong_balances = [leaf.balances[0], leaf.balances[0]];
+        assert!(!merkle_tree.verify_proof_with_expected_leaf(&proof, leaf.hash, wrong_balances));
+    }
+
+    #[test]
+    fn test_entry_from_binance_export_row() {
+        let export = "User ID,Asset,Free,Locked\n\
+                      10485761,BTC,0.00120000,0.00050000\n\
+                      10485761,ETH,42.00000000,0.00000000\n\
+                      20971522,DOGE,1.00000000,1.00000000\n\
+                      31457283,BTC,0.000000015,0.00000000\n\
+                      41943044,ETH,1.5e3,0.00000000\n";
+
+        let asset_map: HashMap<String, usize> =
+            HashMap::from([("BTC".to_string(), 0), ("ETH".to_string(), 1)]);
+        let asset_decimals: HashMap<String, u32> =
+            HashMap::from([("BTC".to_string(), 8), ("ETH".to_string(), 18)]);
+
+        let mut reader = csv::Reader::from_reader(export.as_bytes());
+        assert!(
+            Entry::<N_CURRENCIES>::check_binance_export_header(reader.headers().unwrap()).is_ok()
+        );
+        let rows: Vec<csv::StringRecord> = reader.records().map(|row| row.unwrap()).collect();
+
+        // The amounts are converted to the smallest unit of the asset
+        let entry =
+            Entry::<N_CURRENCIES>::from_binance_export_row(&rows[0], &asset_map, &asset_decimals)
+                .unwrap();
+        assert_eq!(entry.username(), "10485761");
+        assert_eq!(
+            entry.balances(),
+            &[170000.to_biguint().unwrap(), 0.to_biguint().unwrap()]
+        );
+
+        // The trailing zeros may exceed the decimals of the asset
+        let entry =
+            Entry::<N_CURRENCIES>::from_binance_export_row(&rows[1], &asset_map, &asset_decimals)
+                .unwrap();
+        assert_eq!(
+            entry.balances(),
+            &[
+                0.to_biguint().unwrap(),
+                BigUint::parse_bytes(b"42000000000000000000", 10).unwrap()
+            ]
+        );
+
+        assert_eq!(
+            Entry::<N_CURRENCIES>::from_binance_export_row(&rows[2], &asset_map, &asset_decimals),
+            Err(EntryParseError::UnknownAsset("DOGE".to_string()))
+        );
+
+        // An amount more precise than its asset or not in decimal notation is invalid
+        assert_eq!(
+            Entry::<N_CURRENCIES>::from_binance_export_row(&rows[3], &asset_map, &asset_decimals),
+            Err(EntryParseError::InvalidAmount("0.000000015".to_string()))
+        );
+        assert_eq!(
+            Entry::<N_CURRENCIES>::from_binance_export_row(&rows[4], &asset_map, &asset_decimals),
+            Err(EntryParseError::InvalidAmount("1.5e3".to_string()))
+        );
+
+        // An asset without decimals is unknown
+        let asset_decimals_without_eth: HashMap<String, u32> =
+            HashMap::from([("BTC".to_string(), 8)]);
+        assert_eq!(
+            Entry::<N_CURRENCIES>::from_binance_export_row(
+                &rows[1],
+                &asset_map,
+                &asset_decimals_without_eth
+            ),
+            Err(EntryParseError::UnknownAsset("ETH".to_string()))
+        );
+
+        let short_row = csv::StringRecord::from(vec!["51380225", "BTC", "1.00000000"]);
+        assert_eq!(
+            Entry::<N_CURRENCIES>::from_binance_export_row(&short_row, &asset_map, &asset_decimals),
+            Err(EntryParseError::MissingColumn("Locked"))
+        );
+
+        // An export with other columns is rejected
+        let header = csv::StringRecord::from(vec!["User ID", "Coin", "Free", "Locked"]);
+        assert_eq!(
+            Entry::<N_CURRENCIES>::check_binance_export_header(&header),
+            Err(EntryParseError::InvalidHeader(
+                "User ID,Coin,Free,Locked".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_tree_transcript() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let (built_merkle_tree, transcript) =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::build_with_transcript(
+                merkle_tree.entries().to_vec(),
+                merkle_tree.cryptocurrencies().to_vec(),
+            )
+            .unwrap();
+
+        assert_eq!(built_merkle_tree.root(), merkle_tree.root());
+        assert_eq!(transcript.leaf_hashes.len(), 16);
+        assert_eq!(transcript.internal_hashes.len(), 4);
+        assert_eq!(transcript.internal_hashes[3], vec![merkle_tree.root().hash]);
+        assert!(MerkleSumTree::<N_CURRENCIES, N_BYTES>::verify_transcript(
+            &transcript
+        ));
+
+        // Tampering with a leaf hash should be detected
+        let mut tampered_transcript = transcript.clone();
+        tampered_transcript.leaf_hashes[3] = Fp::from(1u64);
+        assert!(!MerkleSumTree::<N_CURRENCIES, N_BYTES>::verify_transcript(
+            &tampered_transcript
+        ));
+
+        // Tampering with a balance sum should be detected
+        let mut tampered_transcript = transcript.clone();
+        tampered_transcript.balance_sums[2][1][0] += Fp::from(1u64);
+        assert!(!MerkleSumTree::<N_CURRENCIES, N_BYTES>::verify_transcript(
+            &tampered_transcript
+        ));
+
+        // A transcript with a missing level should be rejected
+        let mut tampered_transcript = transcript;
+        tampered_transcript.internal_hashes.pop();
+        assert!(!MerkleSumTree::<N_CURRENCIES, N_BYTES>::verify_transcript(
+            &tampered_transcript
+        ));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_assert_invariants() {
+        let mut merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        merkle_tree.assert_invariants();
+
+        // Updating a leaf keeps the invariants and updates the root
+        let new_root = merkle_tree
+            .update_leaf(
+                "RkLzkDun",
+                &[2086.to_biguint().unwrap(), 79732.to_biguint().unwrap()],
+            )
+            .unwrap();
+        assert_eq!(*merkle_tree.root(), new_root);
+        merkle_tree.assert_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The hash of the node 1 at level 2 doesn't match its children")]
+    fn test_assert_invariants_corrupted_node() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut nodes = merkle_tree.nodes().to_vec();
+        nodes[2][1].hash = Fp::from(1u64);
+
+        let corrupted_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            nodes,
+            *merkle_tree.depth(),
+            merkle_tree.entries().to_vec(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        corrupted_merkle_tree.assert_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The hash of the node 1 at level 2 doesn't match its children")]
+    fn test_update_leaf_asserts_invariants() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut nodes = merkle_tree.nodes().to_vec();
+        nodes[2][1].hash = Fp::from(1u64);
+
+        let mut corrupted_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            nodes,
+            *merkle_tree.depth(),
+            merkle_tree.entries().to_vec(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        // Updating a leaf outside of the corrupted subtree recomputes none of its nodes, the invariants check still catches it
+        let username = merkle_tree.entries()[15].username().to_string();
+        corrupted_merkle_tree
+            .update_leaf(&username, &[1u32.into(), 1u32.into()])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_count_unique_usernames() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        assert_eq!(merkle_tree.count_unique_usernames().unwrap(), 16);
+
+        // The zero entries padding the tree are not users
+        let padded_merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_13.csv").unwrap();
+        assert_eq!(padded_merkle_tree.count_unique_usernames().unwrap(), 13);
+
+        // Add the user at index 2 a second time with different balances
+        let mut entries = merkle_tree.entries().to_vec();
+        entries[9] = Entry::new(
+            entries[2].username().to_string(),
+            [1.to_biguint().unwrap(), 1.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        let expected_error = DuplicateError::DuplicateUsername {
+            username_hash: big_uint_to_fp(entries[2].username_as_big_uint()),
+            indices: vec![2, 9],
+        };
+
+        // A tree with duplicate usernames can't be built from its entries
+        let error = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            entries.clone(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<DuplicateError>(),
+            Some(&expected_error)
+        );
+
+        let non_zero_entries: Vec<(usize, Entry<N_CURRENCIES>)> =
+            entries.iter().cloned().enumerate().collect();
+        let error = SparseMerkleSumTree::<N_CURRENCIES, N_BYTES>::new(
+            &non_zero_entries,
+            32,
+            merkle_tree.cryptocurrencies().to_vec(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<DuplicateError>(),
+            Some(&expected_error)
+        );
+
+        // A tree built from its parameters is only checked when counting its users
+        let duplicate_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            merkle_tree.nodes().to_vec(),
+            *merkle_tree.depth(),
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            duplicate_merkle_tree.count_unique_usernames(),
+            Err(expected_error)
+        );
+    }
+
+    #[test]
+    fn test_extend_proof_to_larger_tree() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let entries = merkle_tree.entries();
+        let cryptocurrencies = merkle_tree.cryptocurrencies().to_vec();
+
+        let subtree = |range: std::ops::Range<usize>| {
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+                entries[range].to_vec(),
+                cryptocurrencies.clone(),
+                false,
+            )
+            .unwrap()
+        };
+
+        // Trees of the first 4 and 8 entries, and the trees filling the other half of each of them
+        let smallest_tree = subtree(0..4);
+        let second_quarter_tree = subtree(4..8);
+        let right_half_tree = subtree(8..16);
+
+        let proof = smallest_tree.generate_proof(2).unwrap();
+
+        // Double the tree once
+        let extended_proof = proof.extend_to_larger_tree(
+            second_quarter_tree
+                .get_middle_node_hash_preimage(*second_quarter_tree.depth(), 0)
+                .unwrap(),
+            false,
+        );
+        let half_tree = subtree(0..8);
+        assert_eq!(extended_proof.root.hash, half_tree.root().hash);
+        assert!(half_tree.verify_proof(&extended_proof).is_ok());
+
+        // Double it again, up to the full tree
+        let extended_proof = extended_proof.extend_to_larger_tree(
+            right_half_tree
+                .get_middle_node_hash_preimage(*right_half_tree.depth(), 0)
+                .unwrap(),
+            false,
+        );
+        assert_eq!(extended_proof.root.hash, merkle_tree.root().hash);
+        assert!(merkle_tree.verify_proof(&extended_proof).is_ok());
+        assert_eq!(
+            extended_proof.path_indices,
+            merkle_tree.generate_proof(2).unwrap().path_indices
+        );
+
+        // A proof of the right half is extended with the left half as the left sibling
+        let proof = right_half_tree.generate_proof(1).unwrap();
+        let extended_proof = proof.extend_to_larger_tree(
+            half_tree
+                .get_middle_node_hash_preimage(*half_tree.depth(), 0)
+                .unwrap(),
+            true,
+        );
+        assert!(merkle_tree.verify_proof(&extended_proof).is_ok());
+        assert_eq!(
+            extended_proof.path_indices,
+            merkle_tree.generate_proof(9).unwrap().path_indices
+        );
+
+        // Extending on the wrong side gives another root
+        let wrong_side_proof = proof.extend_to_larger_tree(
+            half_tree
+                .get_middle_node_hash_preimage(*half_tree.depth(), 0)
+                .unwrap(),
+            false,
+        );
+        assert_ne!(wrong_side_proof.root.hash, merkle_tree.root().hash);
+    }
+
+    #[test]
+    fn test_entry_equality_ignores_balances() {
+        let entry = Entry::<N_CURRENCIES>::new(
+            "alice".to_string(),
+            [BigUint::from(1u32), BigUint::from(1u32)],
+        )
+        .unwrap();
+        let same_user = Entry::<N_CURRENCIES>::new(
+            "alice".to_string(),
+            [BigUint::from(2u32), BigUint::from(3u32)],
+        )
+        .unwrap();
+
+        // The entries compare equal although their balances and leaves differ
+        assert_eq!(entry, same_user);
+        assert_eq!(entry.cmp(&same_user), std::cmp::Ordering::Equal);
+        assert_ne!(entry.balances(), same_user.balances());
+        assert_ne!(entry.compute_leaf(), same_user.compute_leaf());
+
+        let other_user = Entry::<N_CURRENCIES>::new(
+            "bob".to_string(),
+            [BigUint::from(1u32), BigUint::from(1u32)],
+        )
+        .unwrap();
+        assert_ne!(entry, other_user);
+    }
+
+    #[test]
+    fn test_entry_ordering_by_username() {
+        let balances = |balance: u32| [BigUint::from(balance), BigUint::from(balance)];
+
+        let entry = Entry::<N_CURRENCIES>::new("alice".to_string(), balances(1)).unwrap();
+
+        let entries: Vec<Entry<N_CURRENCIES>> = ["carol", "bob", "alice", "alice", "bo"]
+            .iter()
+            .enumerate()
+            .map(|(i, username)| Entry::new(username.to_string(), balances(i as u32)).unwrap())
+            .collect();
+
+        // A set keeps the first entry of each user
+        let set: std::collections::BTreeSet<Entry<N_CURRENCIES>> =
+            entries.iter().cloned().collect();
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.get(&entry).unwrap().balances(), &balances(2),);
+
+        // Usernames are sorted by their big-endian integer value, so shorter usernames come first
+        let mut sorted_entries = entries.clone();
+        sorted_entries.sort();
+        let usernames: Vec<&str> = sorted_entries
+            .iter()
+            .map(|entry| entry.username())
+            .collect();
+        assert_eq!(usernames, vec!["bo", "bob", "alice", "alice", "carol"]);
+
+        // The zero entry is the smallest entry
+        assert!(Entry::<N_CURRENCIES>::zero_entry() < sorted_entries[0]);
+        assert!(Entry::<N_CURRENCIES>::zero_entry().is_zero_entry());
+        assert!(!Entry::<N_CURRENCIES>::new(String::new(), balances(1))
+            .unwrap()
+            .is_zero_entry());
+    }
+
+    #[test]
+    fn test_entry_order_differs_from_string_order() {
+        let balances = [BigUint::from(1u32), BigUint::from(1u32)];
+        let entries: Vec<Entry<N_CURRENCIES>> = ["b", "aa"]
+            .iter()
+            .map(|username| Entry::new(username.to_string(), balances.clone()).unwrap())
+            .collect();
+
+        // "b" is 0x62 and "aa" is 0x6161, so "b" comes first as an entry but last as a string
+        let mut sorted_entries = entries.clone();
+        sorted_entries.sort();
+        assert_eq!(sorted_entries[0].username(), "b");
+
+        let mut string_sorted_entries = entries;
+        string_sorted_entries.sort_by(|a, b| a.username().cmp(b.username()));
+        assert_eq!(string_sorted_entries[0].username(), "aa");
+
+        // Null proofs bracket the usernames in string order
+        let merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            string_sorted_entries,
+            vec![],
+            true,
+        )
+        .unwrap();
+        let null_proof = merkle_tree.generate_null_proof("ab").unwrap();
+        assert_eq!(null_proof.lower.as_ref().unwrap().entry.username(), "aa");
+        assert_eq!(null_proof.upper.as_ref().unwrap().entry.username(), "b");
+        assert!(merkle_tree.verify_null_proof(&null_proof));
+    }
+
+    #[test]
+    fn test_verify_proofs_batch() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut proofs: Vec<_> = (0..3)
+            .map(|index| merkle_tree.generate_proof(index).unwrap())
+            .collect();
+        proofs[1].root.hash = 0.into();
+
+        // Each proof gets its own outcome, an invalid proof doesn't affect the others
+        assert_eq!(
+            merkle_tree.verify_proofs_batch(&proofs),
+            vec![
+                Ok(()),
+                Err(MerkleVerifyError::RootHashMismatch {
+                    proof_root: 0.into(),
+                    computed_root: merkle_tree.root().hash,
+                }),
+                Ok(()),
+            ]
+        );
+        assert!(merkle_tree.verify_proofs_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_verify_proofs_rng_lc() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let mut proofs: Vec<_> = (0..16)
+            .map(|index| merkle_tree.generate_proof(index).unwrap())
+            .collect();
+
+        let r = Fp::from(rand::thread_rng().gen::<u64>());
+        assert!(merkle_tree.verify_proofs_rng_lc(&proofs, r));
+        assert!(merkle_tree.verify_proofs_rng_lc(&[], r));
+
+        // A single corrupted balance makes the batch fail
+        let mut invalid_proofs = proofs.clone();
+        invalid_proofs[5].entry = Entry::new(
+            proofs[5].entry.username().to_string(),
+            [BigUint::from(1u32), BigUint::from(1u32)],
+        )
+        .unwrap();
+        assert!(!merkle_tree.verify_proofs_rng_lc(&invalid_proofs, r));
+
+        // As well as a corrupted path
+        proofs[9].path_indices[1] = Fp::from(1) - proofs[9].path_indices[1];
+        assert!(!merkle_tree.verify_proofs_rng_lc(&proofs, r));
+    }
+
+    #[test]
+    fn test_middle_node_at_depth() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+        let depth = *merkle_tree.depth();
+
+        // Rebuild the tree level by level with `Node::middle`
+        let mut level_nodes = merkle_tree.leaves().to_vec();
+        for level in 1..=depth {
+            level_nodes = level_nodes
+                .chunks(2)
+                .map(|pair| {
+                    let node = Node::middle(&pair[0], &pair[1]);
+                    assert_eq!(
+                        Node::middle_at_depth(&pair[0], &pair[1], (depth - level) as u32),
+                        node
+                    );
+                    node
+                })
+                .collect();
+            assert_eq!(level_nodes, merkle_tree.nodes()[level]);
+        }
+
+        assert_eq!(level_nodes[0], *merkle_tree.root());
+    }
+
+    #[test]
+    fn test_single_leaf_tree_proof() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        let single_leaf_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_entries(
+            merkle_tree.entries()[..1].to_vec(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(*single_leaf_tree.depth(), 0);
+        assert_eq!(*single_leaf_tree.root(), merkle_tree.leaves()[0]);
+
+        // The proof of the only leaf has an empty path
+        let proof = single_leaf_tree.generate_proof(0).unwrap();
+        assert!(proof.path_indices.is_empty());
+        assert!(proof.sibling_middle_node_hash_preimages.is_empty());
+        assert!(single_leaf_tree.verify_proof(&proof).is_ok());
+        assert!(single_leaf_tree.generate_proof(1).is_err());
+
+        // The leaf must still be the root
+        let mut tampered_proof = proof.clone();
+        tampered_proof.entry = merkle_tree.entries()[1].clone();
+        assert!(matches!(
+            single_leaf_tree.verify_proof(&tampered_proof),
+            Err(MerkleVerifyError::RootHashMismatch { .. })
+        ));
+
+        // A proof with an empty path is rejected by a deeper tree, even if it claims a root equal to its leaf
+        assert_eq!(
+            merkle_tree.verify_proof(&proof),
+            Err(MerkleVerifyError::EmptyPath { depth: 4 })
+        );
+    }
+
+    #[test]
+    fn test_audit_leaf_range() {
+        let merkle_tree =
+            MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_csv("../csv/entry_16.csv").unwrap();
+
+        assert_eq!(merkle_tree.audit_leaf_range(0, 16).unwrap(), vec![true; 16]);
+        assert_eq!(merkle_tree.audit_leaf_range(3, 11).unwrap(), vec![true; 8]);
+        assert_eq!(merkle_tree.audit_leaf_range(7, 8).unwrap(), vec![true]);
+
+        assert!(merkle_tree.audit_leaf_range(8, 8).is_err());
+        assert!(merkle_tree.audit_leaf_range(0, 17).is_err());
+
+        // Replace the 5th entry without updating the nodes of the tree
+        let mut entries = merkle_tree.entries().to_vec();
+        entries[5] = Entry::new(
+            "AtwIxZHo".to_string(),
+            [35479.to_biguint().unwrap(), 35479.to_biguint().unwrap()],
+        )
+        .unwrap();
+
+        let corrupted_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            merkle_tree.nodes().to_vec(),
+            *merkle_tree.depth(),
+            entries,
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        // The corrupted leaf is invalid, and so is its sibling, whose proof would hash the corrupted entry
+        let mut expected = vec![true; 8];
+        expected[1] = false;
+        expected[2] = false;
+        assert_eq!(
+            corrupted_merkle_tree.audit_leaf_range(3, 11).unwrap(),
+            expected
+        );
+
+        // The other leaves stay valid, even when the range starts right after the corrupted leaf
+        assert_eq!(
+            corrupted_merkle_tree.audit_leaf_range(6, 16).unwrap(),
+            vec![true; 10]
+        );
+
+        // The range audit agrees with the verification of one Merkle proof per leaf
+        let report = corrupted_merkle_tree.generate_audit_report(1);
+        let failed_indices: Vec<usize> = corrupted_merkle_tree
+            .audit_leaf_range(0, 16)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, is_valid)| !**is_valid)
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(failed_indices, report.failed_indices);
+
+        // Corrupt the middle node above the leaves 4 and 5
+        let mut nodes = merkle_tree.nodes().to_vec();
+        nodes[1][2].hash = Fp::from(1);
+
+        let corrupted_merkle_tree = MerkleSumTree::<N_CURRENCIES, N_BYTES>::from_params(
+            *merkle_tree.root(),
+            nodes,
+            *merkle_tree.depth(),
+            merkle_tree.entries().to_vec(),
+            merkle_tree.cryptocurrencies().to_vec(),
+            false,
+        )
+        .unwrap();
+
+        // The proofs of the leaves 4 and 5 recompute the corrupted node from their entries, so they still verify.
+        // The corrupted node is only read by the proofs of the leaves 0 to 3, to hash the sibling of their path at level 2
+        let report = corrupted_merkle_tree.generate_audit_report(1);
+        assert_eq!(report.failed_indices, vec![0, 1, 2, 3]);
+        assert_eq!(
+            corrupted_merkle_tree.audit_leaf_range(2, 6).unwrap(),
+            vec![false, false, true, true]
+        );
+
+        let failed_indices: Vec<usize> = corrupted_merkle_tree
+            .audit_leaf_range(0, 16)
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, is_valid)| !**is_valid)
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(failed_indices, report.failed_indices);
+    }
 }
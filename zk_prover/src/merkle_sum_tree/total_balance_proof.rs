@@ -0,0 +1,39 @@
+use crate::merkle_sum_tree::utils::big_uint_to_fp;
+use crate::merkle_sum_tree::{MerkleSumTree, Tree};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use num_bigint::BigUint;
+
+/// A proof that the root of a Merkle Sum Tree commits to a given total balance per cryptocurrency.
+///
+/// Fields:
+/// * `root_balance`: The balances of the root node
+/// * `root_hash`: The hash of the root node, to be checked against the root committed on-chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct TotalBalanceProof<const N_CURRENCIES: usize> {
+    pub root_balance: [Fp; N_CURRENCIES],
+    pub root_hash: Fp,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Returns the root node data as a TotalBalanceProof
+    pub fn prove_total_balance(&self) -> TotalBalanceProof<N_CURRENCIES> {
+        TotalBalanceProof {
+            root_balance: self.root().balances,
+            root_hash: self.root().hash,
+        }
+    }
+}
+
+/// Verifies that the root balances of `proof` are equal to `claimed_total`.
+///
+/// The proof only binds the total to `proof.root_hash`, the verifier must still check that this hash is the root committed on-chain.
+pub fn verify_total_balance_proof<const N_CURRENCIES: usize>(
+    proof: &TotalBalanceProof<N_CURRENCIES>,
+    claimed_total: &[BigUint; N_CURRENCIES],
+) -> bool {
+    proof
+        .root_balance
+        .iter()
+        .zip(claimed_total.iter())
+        .all(|(root_balance, claimed_balance)| *root_balance == big_uint_to_fp(claimed_balance))
+}
@@ -0,0 +1,95 @@
+use crate::merkle_sum_tree::{Cryptocurrency, Entry, MerkleSumTree, Node};
+use halo2_proofs::halo2curves::bn256::Fr as Fp;
+
+/// A record of every node computed while building a Merkle Sum Tree.
+///
+/// Fields:
+/// * `leaf_hashes`: The hashes of the leaves
+/// * `internal_hashes`: The hashes of the middle nodes, level by level from the level above the leaves up to the root
+/// * `balance_sums`: The balances of the nodes, level by level from the leaves up to the root
+///
+/// The transcript reveals the balances and hashes of every leaf, but not the usernames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeTranscript<const N_CURRENCIES: usize> {
+    pub leaf_hashes: Vec<Fp>,
+    pub internal_hashes: Vec<Vec<Fp>>,
+    pub balance_sums: Vec<Vec<[Fp; N_CURRENCIES]>>,
+}
+
+impl<const N_CURRENCIES: usize, const N_BYTES: usize> MerkleSumTree<N_CURRENCIES, N_BYTES> {
+    /// Builds a Merkle Sum Tree from a vector of entries along with the transcript of its construction
+    pub fn build_with_transcript(
+        entries: Vec<Entry<N_CURRENCIES>>,
+        cryptocurrencies: Vec<Cryptocurrency>,
+    ) -> Result<(Self, TreeTranscript<N_CURRENCIES>), Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let tree = Self::from_entries(entries, cryptocurrencies, false)?;
+        let nodes = tree.nodes();
+
+        let transcript = TreeTranscript {
+            leaf_hashes: nodes[0].iter().map(|node| node.hash).collect(),
+            internal_hashes: nodes[1..]
+                .iter()
+                .map(|level| level.iter().map(|node| node.hash).collect())
+                .collect(),
+            balance_sums: nodes
+                .iter()
+                .map(|level| level.iter().map(|node| node.balances).collect())
+                .collect(),
+        };
+
+        Ok((tree, transcript))
+    }
+
+    /// Verifies a transcript by recomputing every middle node from its children.
+    ///
+    /// The leaves are taken as given, so an auditor can check the construction of the tree without the entries.
+    pub fn verify_transcript(transcript: &TreeTranscript<N_CURRENCIES>) -> bool
+    where
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let depth = transcript.internal_hashes.len();
+
+        if transcript.leaf_hashes.len() != 1 << depth
+            || transcript.balance_sums.len() != depth + 1
+            || transcript.balance_sums[0].len() != transcript.leaf_hashes.len()
+        {
+            return false;
+        }
+
+        let mut children: Vec<Node<N_CURRENCIES>> = transcript
+            .leaf_hashes
+            .iter()
+            .zip(transcript.balance_sums[0].iter())
+            .map(|(hash, balances)| Node {
+                hash: *hash,
+                balances: *balances,
+            })
+            .collect();
+
+        for level in 1..=depth {
+            let hashes = &transcript.internal_hashes[level - 1];
+            let balances = &transcript.balance_sums[level];
+
+            if hashes.len() != children.len() / 2 || balances.len() != hashes.len() {
+                return false;
+            }
+
+            let mut parents = Vec::with_capacity(hashes.len());
+            for (index, pair) in children.chunks(2).enumerate() {
+                let parent = Node::middle_at_depth(&pair[0], &pair[1], (depth - level) as u32);
+                if parent.hash != hashes[index] || parent.balances != balances[index] {
+                    return false;
+                }
+                parents.push(parent);
+            }
+
+            children = parents;
+        }
+
+        true
+    }
+}
@@ -1,7 +1,83 @@
 use crate::merkle_sum_tree::utils::big_uint_to_fp;
 use crate::merkle_sum_tree::Cryptocurrency;
-use crate::merkle_sum_tree::{Entry, MerkleProof, Node};
+use crate::merkle_sum_tree::{Entry, MerkleProof, Node, NullProof};
 use halo2_proofs::halo2curves::bn256::Fr as Fp;
+use std::fmt;
+
+/// The reason why a MerkleProof failed verification.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MerkleVerifyError {
+    /// The node recomputed at `level` doesn't match the node stored in the tree. Level 0 is the leaf level.
+    HashMismatch { level: usize, expected: Fp, got: Fp },
+    /// A root balance recomputed from the proof doesn't match the corresponding root balance of the proof.
+    BalanceMismatch { expected: Fp, got: Fp },
+    /// The root hash recomputed from the proof doesn't match the root hash of the proof.
+    RootHashMismatch { proof_root: Fp, computed_root: Fp },
+    /// The proof has no path, while the tree has depth `depth`. Only the proof of a single leaf tree has no path.
+    EmptyPath { depth: usize },
+    /// The proof has `sibling_middle_nodes` sibling middle node preimages for a path of `path_length` levels, one less is expected.
+    PathLengthMismatch {
+        path_length: usize,
+        sibling_middle_nodes: usize,
+    },
+}
+
+impl fmt::Display for MerkleVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleVerifyError::HashMismatch {
+                level,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Hash mismatch at level {}: expected {:?}, got {:?}",
+                level, expected, got
+            ),
+            MerkleVerifyError::BalanceMismatch { expected, got } => write!(
+                f,
+                "Root balance mismatch: expected {:?}, got {:?}",
+                expected, got
+            ),
+            MerkleVerifyError::RootHashMismatch {
+                proof_root,
+                computed_root,
+            } => write!(
+                f,
+                "Root hash mismatch: proof root {:?}, computed root {:?}",
+                proof_root, computed_root
+            ),
+            MerkleVerifyError::EmptyPath { depth } => {
+                write!(f, "Empty proof path for a tree of depth {}", depth)
+            }
+            MerkleVerifyError::PathLengthMismatch {
+                path_length,
+                sibling_middle_nodes,
+            } => write!(
+                f,
+                "{} sibling middle nodes for a proof path of {} levels",
+                sibling_middle_nodes, path_length
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MerkleVerifyError {}
+
+/// A node recomputed while verifying a MerkleProof.
+///
+/// Fields:
+/// * `level`: The level of the node. Level 0 is the leaf level
+/// * `computed_hash`: The hash of the node recomputed from the proof
+/// * `computed_balances`: The balances of the node recomputed from the proof
+/// * `expected_hash`: The hash of the node stored in the tree at the same position, if any
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEntry<const N_CURRENCIES: usize> {
+    pub level: usize,
+    pub computed_hash: Fp,
+    pub computed_balances: [Fp; N_CURRENCIES],
+    pub expected_hash: Option<Fp>,
+}
 
 /// A trait representing the basic operations for a Merkle-Sum-like Tree.
 pub trait Tree<const N_CURRENCIES: usize> {
@@ -11,14 +87,25 @@ pub trait Tree<const N_CURRENCIES: usize> {
     /// Returns the depth of the tree.
     fn depth(&self) -> &usize;
 
-    /// Returns a slice of the nodes.
-    fn nodes(&self) -> &[Vec<Node<N_CURRENCIES>>];
+    /// Returns the node at `index` in `level`, level 0 being the leaf level, or `None` if the tree has no such node.
+    fn get_node(&self, level: usize, index: usize) -> Option<&Node<N_CURRENCIES>>;
+
+    /// Returns the number of leaves of the tree, including the zero entries padding it.
+    fn leaves_count(&self) -> usize {
+        1 << *self.depth()
+    }
 
     /// Returns the cryptocurrencies whose balances are in the tree. The order of cryptocurrencies and balances is supposed to agree for all the entries.
     fn cryptocurrencies(&self) -> &[Cryptocurrency];
 
     fn get_entry(&self, index: usize) -> &Entry<N_CURRENCIES>;
 
+    /// Returns whether the entries of the tree are sorted by username, as required by null proofs.
+    /// A tree is assumed not to be sorted unless it says otherwise.
+    fn is_sorted(&self) -> bool {
+        false
+    }
+
     /// Returns the hash preimage of a middle node.
     fn get_middle_node_hash_preimage(
         &self,
@@ -32,14 +119,12 @@ pub trait Tree<const N_CURRENCIES: usize> {
             return Err(Box::from("Invalid depth"));
         }
 
-        self.nodes()
-            .get(level)
-            .and_then(|layer| layer.get(index))
+        self.get_node(level, index)
             .ok_or_else(|| Box::<dyn std::error::Error>::from("Node not found"))?;
 
         // Assuming the left and right children are stored in order
-        let left_child = &self.nodes()[level - 1][2 * index];
-        let right_child = &self.nodes()[level - 1][2 * index + 1];
+        let left_child = self.get_node(level - 1, 2 * index).unwrap();
+        let right_child = self.get_node(level - 1, 2 * index + 1).unwrap();
 
         // Constructing preimage
         let mut preimage = [Fp::zero(); N_CURRENCIES + 2];
@@ -82,6 +167,9 @@ pub trait Tree<const N_CURRENCIES: usize> {
     }
 
     /// Generates a MerkleProof for the user with the given index.
+    ///
+    /// The path from the leaf to the root is walked with a loop over the levels, so the stack usage doesn't grow with the depth of the tree.
+    /// In a single leaf tree, the leaf is the root: the proof has an empty path and, as there is no sibling leaf, a zero sibling leaf preimage.
     fn generate_proof(
         &self,
         index: usize,
@@ -90,20 +178,21 @@ pub trait Tree<const N_CURRENCIES: usize> {
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        let nodes = self.nodes();
         let depth = *self.depth();
         let root = self.root();
 
-        if index >= nodes[0].len() {
+        if self.get_node(0, index).is_none() {
             return Err(Box::from("Index out of bounds"));
         }
 
-        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(depth - 1);
+        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(depth.saturating_sub(1));
 
-        let sibling_leaf_index = if index % 2 == 0 { index + 1 } else { index - 1 };
-
-        let sibling_leaf_node_hash_preimage: [Fp; N_CURRENCIES + 1] =
-            self.get_leaf_node_hash_preimage(sibling_leaf_index)?;
+        let sibling_leaf_node_hash_preimage: [Fp; N_CURRENCIES + 1] = if depth == 0 {
+            [Fp::zero(); N_CURRENCIES + 1]
+        } else {
+            let sibling_leaf_index = if index % 2 == 0 { index + 1 } else { index - 1 };
+            self.get_leaf_node_hash_preimage(sibling_leaf_index)?
+        };
         let mut path_indices = vec![Fp::zero(); depth];
         let mut current_index = index;
 
@@ -111,7 +200,7 @@ pub trait Tree<const N_CURRENCIES: usize> {
             let position = current_index % 2;
             let sibling_index = current_index - position + (1 - position);
 
-            if sibling_index < nodes[level].len() && level != 0 {
+            if self.get_node(level, sibling_index).is_some() && level != 0 {
                 // Fetch hash preimage for sibling middle nodes
                 let sibling_node_preimage =
                     self.get_middle_node_hash_preimage(level, sibling_index)?;
@@ -126,7 +215,57 @@ pub trait Tree<const N_CURRENCIES: usize> {
 
         Ok(MerkleProof {
             entry,
-            root: root.clone(),
+            root: *root,
+            sibling_leaf_node_hash_preimage,
+            sibling_middle_node_hash_preimages,
+            path_indices,
+        })
+    }
+
+    /// Generates the same MerkleProof as `generate_proof` for the user with the given index.
+    ///
+    /// The `(level, index)` pairs of the nodes on the path are pushed on an explicit stack from the root down, then popped from the leaf up,
+    /// so no call is nested per level whatever the depth of the tree.
+    fn get_proof_iterative(
+        &self,
+        leaf_index: usize,
+    ) -> Result<MerkleProof<N_CURRENCIES>, Box<dyn std::error::Error>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let depth = *self.depth();
+
+        if self.get_node(0, leaf_index).is_none() {
+            return Err(Box::from("Index out of bounds"));
+        }
+
+        let mut stack: Vec<(usize, usize)> = (0..depth)
+            .rev()
+            .map(|level| (level, leaf_index >> level))
+            .collect();
+
+        let mut sibling_leaf_node_hash_preimage = [Fp::zero(); N_CURRENCIES + 1];
+        let mut sibling_middle_node_hash_preimages = Vec::with_capacity(depth.saturating_sub(1));
+        let mut path_indices = Vec::with_capacity(depth);
+
+        while let Some((level, index)) = stack.pop() {
+            let sibling_index = index ^ 1;
+
+            if level == 0 {
+                sibling_leaf_node_hash_preimage =
+                    self.get_leaf_node_hash_preimage(sibling_index)?;
+            } else if self.get_node(level, sibling_index).is_some() {
+                sibling_middle_node_hash_preimages
+                    .push(self.get_middle_node_hash_preimage(level, sibling_index)?);
+            }
+
+            path_indices.push(Fp::from((index % 2) as u64));
+        }
+
+        Ok(MerkleProof {
+            entry: self.get_entry(leaf_index).clone(),
+            root: *self.root(),
             sibling_leaf_node_hash_preimage,
             sibling_middle_node_hash_preimages,
             path_indices,
@@ -134,52 +273,324 @@ pub trait Tree<const N_CURRENCIES: usize> {
     }
 
     /// Verifies a MerkleProof.
-    fn verify_proof(&self, proof: &MerkleProof<N_CURRENCIES>) -> bool
+    ///
+    /// Returns a `MerkleVerifyError` describing why the proof is invalid. When the computed root hash doesn't match the root hash of the proof,
+    /// the nodes of this tree are used to locate the first level at which the recomputed path diverges.
+    ///
+    /// A proof with an empty path is only accepted by a single leaf tree, in which case the leaf computed from the entry must be the root of the proof.
+    fn verify_proof(&self, proof: &MerkleProof<N_CURRENCIES>) -> Result<(), MerkleVerifyError>
     where
         [usize; N_CURRENCIES + 1]: Sized,
         [usize; N_CURRENCIES + 2]: Sized,
     {
-        let mut node = proof.entry.compute_leaf();
+        check_path_lengths(proof, *self.depth())?;
+
+        let path = compute_path_nodes(proof);
+        let computed_root = &path[path.len() - 1];
+
+        if computed_root.hash != proof.root.hash {
+            let mut index = leaf_index_from_path_indices(&proof.path_indices);
+            for (level, node) in path.iter().enumerate() {
+                if let Some(stored_node) = self.get_node(level, index) {
+                    if stored_node.hash != node.hash {
+                        return Err(MerkleVerifyError::HashMismatch {
+                            level,
+                            expected: stored_node.hash,
+                            got: node.hash,
+                        });
+                    }
+                }
+                index /= 2;
+            }
 
-        let sibling_leaf_node =
-            Node::<N_CURRENCIES>::leaf_node_from_preimage(&proof.sibling_leaf_node_hash_preimage);
+            return Err(MerkleVerifyError::RootHashMismatch {
+                proof_root: proof.root.hash,
+                computed_root: computed_root.hash,
+            });
+        }
 
-        let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-        for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-            *balance = node.balances[i] + sibling_leaf_node.balances[i];
+        for (expected, got) in proof.root.balances.iter().zip(&computed_root.balances) {
+            if expected != got {
+                return Err(MerkleVerifyError::BalanceMismatch {
+                    expected: *expected,
+                    got: *got,
+                });
+            }
         }
 
-        if proof.path_indices[0] == 0.into() {
-            hash_preimage[N_CURRENCIES] = node.hash;
-            hash_preimage[N_CURRENCIES + 1] = sibling_leaf_node.hash;
-            node = Node::middle_node_from_preimage(&hash_preimage);
-        } else {
-            hash_preimage[N_CURRENCIES] = sibling_leaf_node.hash;
-            hash_preimage[N_CURRENCIES + 1] = node.hash;
-            node = Node::middle_node_from_preimage(&hash_preimage);
+        Ok(())
+    }
+
+    /// Verifies a MerkleProof, returning `true` if the proof is valid. Kept for backward compatibility with the boolean API.
+    fn verify_proof_bool(&self, proof: &MerkleProof<N_CURRENCIES>) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        self.verify_proof(proof).is_ok()
+    }
+
+    /// Verifies a MerkleProof whose leaf is known from a trusted source.
+    ///
+    /// Returns `true` if the leaf computed from `proof.entry` has hash `expected_leaf_hash` and balances `expected_balances`, and the proof is valid.
+    fn verify_proof_with_expected_leaf(
+        &self,
+        proof: &MerkleProof<N_CURRENCIES>,
+        expected_leaf_hash: Fp,
+        expected_balances: [Fp; N_CURRENCIES],
+    ) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let leaf = proof.entry.compute_leaf();
+        if leaf.hash != expected_leaf_hash || leaf.balances != expected_balances {
+            return false;
         }
 
-        for i in 1..proof.path_indices.len() {
-            let sibling_node = Node::<N_CURRENCIES>::middle_node_from_preimage(
-                &proof.sibling_middle_node_hash_preimages[i - 1],
+        self.verify_proof(proof).is_ok()
+    }
+
+    /// Verifies a MerkleProof, returning along with the result the nodes recomputed on the path from the leaf to the root.
+    ///
+    /// The trace is returned whether the proof is valid or not. For an invalid proof, the first entry whose `computed_hash` differs from its `expected_hash` locates the corrupted level.
+    /// A proof whose path can't be recomputed, as checked by `verify_proof`, is rejected with a `MerkleVerifyError` and no trace.
+    fn verify_proof_traced(
+        &self,
+        proof: &MerkleProof<N_CURRENCIES>,
+    ) -> Result<(bool, Vec<TraceEntry<N_CURRENCIES>>), MerkleVerifyError>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        check_path_lengths(proof, *self.depth())?;
+
+        let path = compute_path_nodes(proof);
+        let computed_root = &path[path.len() - 1];
+        let is_valid =
+            computed_root.hash == proof.root.hash && computed_root.balances == proof.root.balances;
+
+        let mut trace = Vec::with_capacity(path.len());
+        let mut index = leaf_index_from_path_indices(&proof.path_indices);
+        for (level, node) in path.iter().enumerate() {
+            trace.push(TraceEntry {
+                level,
+                computed_hash: node.hash,
+                computed_balances: node.balances,
+                expected_hash: self
+                    .get_node(level, index)
+                    .map(|stored_node| stored_node.hash),
+            });
+            index /= 2;
+        }
+
+        Ok((is_valid, trace))
+    }
+
+    /// Verifies a MerkleProof like `verify_proof_traced`, logging each entry of the trace with `tracing::debug!`.
+    ///
+    /// Only available with the `tracing` feature, so that `verify_proof` and the default build don't depend on a logger.
+    #[cfg(feature = "tracing")]
+    fn verify_proof_traced_debug(&self, proof: &MerkleProof<N_CURRENCIES>) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let (is_valid, trace) = match self.verify_proof_traced(proof) {
+            Ok(traced) => traced,
+            Err(error) => {
+                tracing::debug!(%error, "Merkle proof rejected");
+                return false;
+            }
+        };
+
+        for trace_entry in &trace {
+            tracing::debug!(
+                level = trace_entry.level,
+                computed_hash = ?trace_entry.computed_hash,
+                computed_balances = ?trace_entry.computed_balances,
+                expected_hash = ?trace_entry.expected_hash,
+                "Merkle proof trace"
             );
+        }
+        tracing::debug!(is_valid, "Merkle proof verified");
+
+        is_valid
+    }
+
+    /// Verifies each of the MerkleProofs with `verify_proof`, returning the outcomes in the order of the proofs.
+    fn verify_proofs_batch(
+        &self,
+        proofs: &[MerkleProof<N_CURRENCIES>],
+    ) -> Vec<Result<(), MerkleVerifyError>>
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        proofs
+            .iter()
+            .map(|proof| self.verify_proof(proof))
+            .collect()
+    }
+
+    /// Verifies a batch of MerkleProofs with a random linear combination, returning `true` if all the proofs are valid with overwhelming probability.
+    ///
+    /// The root of each proof is still recomputed from its leaf, so the batch costs as many hashes as verifying the proofs one by one.
+    /// What is batched is the comparison: the differences between the recomputed and the claimed roots, for the hash and each balance, are combined as `sum(r^i * diff_i)` and checked to be zero at once.
+    ///
+    /// If any proof is invalid, the combination is a nonzero polynomial of degree less than `proofs.len()` in `r`, so it vanishes with probability at most `(proofs.len() - 1) / p` over a uniformly random `r`, where `p` is the field modulus.
+    /// This bound only holds if `r` is chosen after the proofs are fixed, for example by hashing them (Fiat-Shamir). A prover who knows `r` in advance can craft invalid proofs that pass.
+    fn verify_proofs_rng_lc(&self, proofs: &[MerkleProof<N_CURRENCIES>], r: Fp) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        let mut weight = Fp::from(1);
+        let mut hash_combination = Fp::zero();
+        let mut balance_combinations = [Fp::zero(); N_CURRENCIES];
+
+        for proof in proofs {
+            if check_path_lengths(proof, *self.depth()).is_err() {
+                return false;
+            }
+
+            let path = compute_path_nodes(proof);
+            let computed_root = &path[path.len() - 1];
+
+            hash_combination += weight * (computed_root.hash - proof.root.hash);
+            for (combination, (computed, claimed)) in balance_combinations
+                .iter_mut()
+                .zip(computed_root.balances.iter().zip(&proof.root.balances))
+            {
+                *combination += weight * (computed - claimed);
+            }
+
+            weight *= r;
+        }
 
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance = node.balances[i] + sibling_node.balances[i];
+        hash_combination == Fp::zero()
+            && balance_combinations
+                .iter()
+                .all(|combination| *combination == Fp::zero())
+    }
+
+    /// Verifies a NullProof, returning `true` if the proof shows that `null_proof.username` is not in the tree.
+    ///
+    /// Both Merkle Proofs must be valid against the root of this tree and belong to adjacent leaves, with the username strictly in between their usernames.
+    /// A missing `lower` proof must be replaced by an `upper` proof for the first leaf, and a missing `upper` proof by a `lower` proof for the last leaf.
+    /// Adjacent leaves only bracket the usernames absent from the tree if it is sorted, so the proofs of an unsorted tree are always rejected.
+    fn verify_null_proof(&self, null_proof: &NullProof<N_CURRENCIES>) -> bool
+    where
+        [usize; N_CURRENCIES + 1]: Sized,
+        [usize; N_CURRENCIES + 2]: Sized,
+    {
+        if !self.is_sorted() {
+            return false;
+        }
+
+        let username = null_proof.username.as_str();
+
+        for proof in [&null_proof.lower, &null_proof.upper].into_iter().flatten() {
+            // A proof valid against the root of another tree says nothing about the usernames of this tree
+            if proof.root != *self.root() || self.verify_proof(proof).is_err() {
+                return false;
+            }
+        }
+
+        if let Some(lower) = &null_proof.lower {
+            if lower.entry.is_zero_entry() || lower.entry.username() >= username {
+                return false;
             }
+        }
 
-            if proof.path_indices[i] == 0.into() {
-                hash_preimage[N_CURRENCIES] = node.hash;
-                hash_preimage[N_CURRENCIES + 1] = sibling_node.hash;
-                node = Node::middle_node_from_preimage(&hash_preimage);
-            } else {
-                hash_preimage[N_CURRENCIES] = sibling_node.hash;
-                hash_preimage[N_CURRENCIES + 1] = node.hash;
-                node = Node::middle_node_from_preimage(&hash_preimage);
+        if let Some(upper) = &null_proof.upper {
+            if !upper.entry.is_zero_entry() && upper.entry.username() <= username {
+                return false;
             }
         }
 
-        proof.root.hash == node.hash && proof.root.balances == node.balances
+        match (&null_proof.lower, &null_proof.upper) {
+            (Some(lower), Some(upper)) => {
+                leaf_index_from_path_indices(&lower.path_indices) + 1
+                    == leaf_index_from_path_indices(&upper.path_indices)
+            }
+            (None, Some(upper)) => leaf_index_from_path_indices(&upper.path_indices) == 0,
+            (Some(lower), None) => {
+                leaf_index_from_path_indices(&lower.path_indices) + 1
+                    == 1 << lower.path_indices.len()
+            }
+            (None, None) => false,
+        }
     }
 }
+
+/// Checks that the path of `proof` can be recomputed: it must be empty only for a tree of depth 0,
+/// and have a sibling middle node for each of its levels but the leaf level.
+fn check_path_lengths<const N_CURRENCIES: usize>(
+    proof: &MerkleProof<N_CURRENCIES>,
+    depth: usize,
+) -> Result<(), MerkleVerifyError>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let path_length = proof.path_indices.len();
+
+    if path_length == 0 && depth != 0 {
+        return Err(MerkleVerifyError::EmptyPath { depth });
+    }
+
+    if proof.sibling_middle_node_hash_preimages.len() != path_length.saturating_sub(1) {
+        return Err(MerkleVerifyError::PathLengthMismatch {
+            path_length,
+            sibling_middle_nodes: proof.sibling_middle_node_hash_preimages.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Recomputes the nodes on the path from the leaf of `proof.entry` to the root.
+/// The first element is the leaf node, the last element is the computed root.
+fn compute_path_nodes<const N_CURRENCIES: usize>(
+    proof: &MerkleProof<N_CURRENCIES>,
+) -> Vec<Node<N_CURRENCIES>>
+where
+    [usize; N_CURRENCIES + 1]: Sized,
+    [usize; N_CURRENCIES + 2]: Sized,
+{
+    let depth = proof.path_indices.len();
+    let mut path = Vec::with_capacity(depth + 1);
+    let mut node = proof.entry.compute_leaf();
+
+    for (level, path_index) in proof.path_indices.iter().enumerate() {
+        let sibling_node = if level == 0 {
+            Node::<N_CURRENCIES>::leaf_node_from_preimage(&proof.sibling_leaf_node_hash_preimage)
+        } else {
+            Node::<N_CURRENCIES>::middle_node_from_preimage(
+                &proof.sibling_middle_node_hash_preimages[level - 1],
+            )
+        };
+
+        // The parent is at level `level + 1`, at distance `depth - level - 1` from the root
+        let parent_depth = (depth - level - 1) as u32;
+        let parent = if *path_index == Fp::zero() {
+            Node::middle_at_depth(&node, &sibling_node, parent_depth)
+        } else {
+            Node::middle_at_depth(&sibling_node, &node, parent_depth)
+        };
+
+        path.push(node);
+        node = parent;
+    }
+
+    path.push(node);
+    path
+}
+
+/// Returns the index of the leaf whose path is described by `path_indices`
+fn leaf_index_from_path_indices(path_indices: &[Fp]) -> usize {
+    path_indices.iter().rev().fold(0, |index, path_index| {
+        2 * index + usize::from(*path_index != Fp::zero())
+    })
+}
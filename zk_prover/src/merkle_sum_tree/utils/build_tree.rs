@@ -37,14 +37,14 @@ where
     }
 
     for (index, leaf) in leaves.iter().enumerate() {
-        tree[0][index] = leaf.clone();
+        tree[0][index] = *leaf;
     }
 
     for level in 1..=depth {
-        build_middle_level(level, &mut tree)
+        build_middle_level(level, depth, &mut tree)
     }
 
-    let root = tree[depth][0].clone();
+    let root = tree[depth][0];
     *nodes = tree;
     Ok(root)
 }
@@ -63,8 +63,8 @@ where
         .map(|entry| {
             // If the entry is the zero entry then we return the precomputed zero leaf
             // Otherwise, we compute the leaf as usual
-            if entry == &Entry::<N_CURRENCIES>::zero_entry() {
-                zero_leaf.clone()
+            if entry.is_zero_entry() {
+                zero_leaf
             } else {
                 entry.compute_leaf()
             }
@@ -74,24 +74,25 @@ where
     leaves
 }
 
-fn build_middle_level<const N_CURRENCIES: usize>(level: usize, tree: &mut [Vec<Node<N_CURRENCIES>>])
-where
+fn build_middle_level<const N_CURRENCIES: usize>(
+    level: usize,
+    depth: usize,
+    tree: &mut [Vec<Node<N_CURRENCIES>>],
+) where
     [usize; N_CURRENCIES + 2]: Sized,
 {
+    // The nodes of `level` are at distance `depth - level` from the root
+    let node_depth = (depth - level) as u32;
+
     let results: Vec<Node<N_CURRENCIES>> = (0..tree[level - 1].len())
         .into_par_iter()
         .step_by(2)
         .map(|index| {
-            let mut hash_preimage = [Fp::zero(); N_CURRENCIES + 2];
-
-            for (i, balance) in hash_preimage.iter_mut().enumerate().take(N_CURRENCIES) {
-                *balance =
-                    tree[level - 1][index].balances[i] + tree[level - 1][index + 1].balances[i];
-            }
-
-            hash_preimage[N_CURRENCIES] = tree[level - 1][index].hash;
-            hash_preimage[N_CURRENCIES + 1] = tree[level - 1][index + 1].hash;
-            Node::middle_node_from_preimage(&hash_preimage)
+            Node::middle_at_depth(
+                &tree[level - 1][index],
+                &tree[level - 1][index + 1],
+                node_depth,
+            )
         })
         .collect();
 
@@ -1,5 +1,30 @@
 use halo2_proofs::halo2curves::{bn256::Fr as Fp, group::ff::PrimeField};
 use num_bigint::BigUint;
+use std::fmt;
+
+/// Errors returned when converting raw bytes to a Field Element
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConversionError {
+    /// The input is longer than 31 bytes, so it is not guaranteed to fit in a Field Element
+    InputTooLong(usize),
+    /// The input is not the canonical representation of a Field Element, namely it is larger than the field modulus
+    NonCanonical,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::InputTooLong(len) => {
+                write!(f, "Input of {} bytes is longer than 31 bytes", len)
+            }
+            ConversionError::NonCanonical => {
+                write!(f, "Input is not a canonical field element representation")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
 
 /// Return a BigUint representation of the username
 pub fn big_intify_username(username: &str) -> BigUint {
@@ -15,3 +40,23 @@ pub fn big_uint_to_fp(big_uint: &BigUint) -> Fp {
 pub fn fp_to_big_uint(f: Fp) -> BigUint {
     BigUint::from_bytes_le(f.to_bytes().as_slice())
 }
+
+/// Converts big-endian bytes to a Field Element without going through a BigUint.
+/// Inputs longer than 31 bytes are rejected so that the value always fits in a Field Element.
+pub fn fp_from_be_bytes(bytes: &[u8]) -> Result<Fp, ConversionError> {
+    if bytes.len() > 31 {
+        return Err(ConversionError::InputTooLong(bytes.len()));
+    }
+
+    let mut le_bytes = [0u8; 32];
+    for (le_byte, be_byte) in le_bytes.iter_mut().zip(bytes.iter().rev()) {
+        *le_byte = *be_byte;
+    }
+
+    fp_from_le_bytes(&le_bytes)
+}
+
+/// Converts the 32 bytes little-endian representation of a Field Element to a Field Element
+pub fn fp_from_le_bytes(bytes: &[u8; 32]) -> Result<Fp, ConversionError> {
+    Option::from(Fp::from_bytes(bytes)).ok_or(ConversionError::NonCanonical)
+}